@@ -116,11 +116,12 @@ export function useGame() {
     try {
       setIsLoading(true);
       setError(null);
-      await gameService.makeMove(
+      await gameService.attack(
         gameAccount,
         fromTerritory.id,
         toTerritory.id,
-        troops
+        troops,
+        toTerritory.owner
       );
       await fetchGameState();
     } catch (err) {
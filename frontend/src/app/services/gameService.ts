@@ -176,11 +176,47 @@ export class GameService {
       .rpc();
   }
 
+  // Treaties are keyed by the pair of players in byte order, whoever proposed
+  private async findTreatyPDA(gameAccount: PublicKey, a: PublicKey, b: PublicKey): Promise<PublicKey> {
+    const [first, second] = Buffer.compare(a.toBuffer(), b.toBuffer()) < 0 ? [a, b] : [b, a];
+    const [pda] = await PublicKey.findProgramAddress(
+      [Buffer.from('treaty'), gameAccount.toBuffer(), first.toBuffer(), second.toBuffer()],
+      this.program.programId
+    );
+    return pda;
+  }
+
+  private async findProtocolConfigPDA(): Promise<PublicKey> {
+    const [pda] = await PublicKey.findProgramAddress(
+      [Buffer.from('config')],
+      this.program.programId
+    );
+    return pda;
+  }
+
+  private async findListingPDA(gameAccount: PublicKey): Promise<PublicKey> {
+    const [pda] = await PublicKey.findProgramAddress(
+      [Buffer.from('listing'), gameAccount.toBuffer()],
+      this.program.programId
+    );
+    return pda;
+  }
+
+  private async findProfilePDA(wallet: PublicKey): Promise<PublicKey> {
+    const [pda] = await PublicKey.findProgramAddress(
+      [Buffer.from('profile'), wallet.toBuffer()],
+      this.program.programId
+    );
+    return pda;
+  }
+
+  // `defender` is the target territory's owner, or null when it is neutral
   async attack(
     gameAccount: PublicKey,
     fromTerritory: number,
     toTerritory: number,
-    attackingDice: number
+    attackingDice: number,
+    defender: PublicKey | null
   ): Promise<void> {
     const provider = this.program.provider as AnchorProvider;
     if (!provider.wallet.publicKey) {
@@ -188,21 +224,35 @@ export class GameService {
     }
 
     const walletPublicKey = provider.wallet.publicKey;
-    const playerAccount = await this.findPlayerAccountPDA(gameAccount);
-    const territoryAccount = await this.findTerritoryAccountPDA(gameAccount);
+    // The board and player list are separate accounts recorded on the game
+    const game = (await this.program.account.game.fetch(gameAccount)) as unknown as {
+      board: PublicKey;
+      playerAccount: PublicKey;
+    };
+
+    // The program checks the pair's treaty on every attack against another player
+    const remainingAccounts = defender
+      ? [{
+          pubkey: await this.findTreatyPDA(gameAccount, walletPublicKey, defender),
+          isSigner: false,
+          isWritable: false,
+        }]
+      : [];
 
     await this.program.methods
       .attack(fromTerritory, toTerritory, attackingDice)
       .accounts({
+        protocolConfig: await this.findProtocolConfigPDA(),
         game: gameAccount,
-        territoryAccount,
-        playerAccount,
+        board: game.board,
+        playerAccount: game.playerAccount,
+        listing: await this.findListingPDA(gameAccount),
+        playerProfile: await this.findProfilePDA(walletPublicKey),
         player: walletPublicKey,
-        territoryProgram: this.territoryProgram.programId,
-        territoryState: territoryAccount,
         playerProgram: this.playerProgram.programId,
-        playerState: playerAccount,
+        playerState: await this.findPlayerAccountPDA(gameAccount),
       })
+      .remainingAccounts(remainingAccounts)
       .rpc();
   }
 
@@ -1,4 +1,7 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
 
 use std::hash::Hash;
 
@@ -30,44 +33,234 @@ fn string_to_color(color: &str) -> Result<PlayerColor> {
 pub mod risk_game {
     use super::*;
 
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        treasury: Pubkey,
+        protocol_fee_bps: u16,
+    ) -> Result<()> {
+        require!(
+            protocol_fee_bps <= Config::MAX_PROTOCOL_FEE_BPS,
+            ErrorCode::InvalidProtocolFee
+        );
+
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.treasury = treasury;
+        config.protocol_fee_bps = protocol_fee_bps;
+        config.bump = ctx.bumps.config;
+        Ok(())
+    }
+
+    // Games already created keep the fee they started with, see `Vault::protocol_fee_bps`
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        treasury: Pubkey,
+        protocol_fee_bps: u16,
+    ) -> Result<()> {
+        require!(
+            protocol_fee_bps <= Config::MAX_PROTOCOL_FEE_BPS,
+            ErrorCode::InvalidProtocolFee
+        );
+
+        let config = &mut ctx.accounts.config;
+        config.treasury = treasury;
+        config.protocol_fee_bps = protocol_fee_bps;
+        Ok(())
+    }
+
+    pub fn set_admin(ctx: Context<UpdateConfig>, new_admin: Pubkey) -> Result<()> {
+        ctx.accounts.config.admin = new_admin;
+        Ok(())
+    }
+
+    // Halts every player-facing instruction here and in the territory and player programs.
+    // Game clocks stop with them: lifting a pause restarts every turn timer.
+    pub fn set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        if config.paused && !paused {
+            config.resumed_at = Clock::get()?.unix_timestamp;
+        }
+        config.paused = paused;
+        Ok(())
+    }
+
+    // Emergency stop for a single game. Every seat gets its entry fee back; the wallets
+    // of all seats are passed in seat order as remaining accounts, each followed by its
+    // token account if the game is token-staked.
+    pub fn force_end_game<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ForceEndGame<'info>>,
+    ) -> Result<()> {
+        let game = &ctx.accounts.game;
+        require!(
+            game.state == GameState::Setup || game.state == GameState::InProgress,
+            ErrorCode::GameAlreadyEnded
+        );
+
+        let players = &ctx.accounts.player_account.players;
+        let accounts_per_player = if game.config.stake_mint.is_some() {
+            2
+        } else {
+            1
+        };
+        require!(
+            ctx.remaining_accounts.len() == players.len() * accounts_per_player,
+            ErrorCode::PlayerNotFound
+        );
+
+        for (seat, accounts) in ctx
+            .remaining_accounts
+            .chunks(accounts_per_player)
+            .enumerate()
+        {
+            let player = &accounts[0];
+            require_keys_eq!(
+                player.key(),
+                players[seat].pubkey,
+                ErrorCode::PlayerNotFound
+            );
+
+            let holder = accounts
+                .get(1)
+                .map(Account::<TokenAccount>::try_from)
+                .transpose()?;
+            if let Some(holder) = &holder {
+                require_keys_eq!(holder.owner, player.key(), ErrorCode::StakeAccountMissing);
+            }
+            refund_entry_fee(
+                game,
+                &mut ctx.accounts.vault,
+                player,
+                StakeTokenAccounts {
+                    holder: holder.as_ref(),
+                    vault: ctx.accounts.vault_token_account.as_ref(),
+                    token_program: ctx.accounts.token_program.as_ref(),
+                },
+            )?;
+        }
+
+        ctx.accounts.game.state = GameState::Aborted;
+        ctx.accounts.listing.sync(&ctx.accounts.game, players.len());
+        msg!("Game {} ended by the admin", ctx.accounts.game.key());
+        Ok(())
+    }
+
     pub fn initialize_game(
         ctx: Context<InitializeGame>,
         color: String,
         territories: Vec<Territory>,
         continents: Vec<Continent>,
+        config: GameConfig,
+        team_id: Option<u8>,
     ) -> Result<()> {
+        validate_game_config(&config)?;
+        validate_team(&config.rules, team_id)?;
+        require!(
+            config.mission_dealer != Some(ctx.accounts.creator.key()),
+            ErrorCode::DealerCannotPlay
+        );
+        require!(
+            ctx.accounts.stake_mint.as_ref().map(|m| m.key()) == config.stake_mint,
+            ErrorCode::StakeAccountMissing
+        );
+
         let game = &mut ctx.accounts.game;
-        let territory_account = &mut ctx.accounts.territory_account;
+        let board = &mut ctx.accounts.board.load_init()?;
         let player_account = &mut ctx.accounts.player_account;
 
-        // Initialize game account
-        game.current_player = ctx.accounts.creator.key();
-        game.turn = 1;
-        game.state = GameState::Setup;
-        game.current_phase = TurnPhase::Reinforcement;
-        game.cards_sets_turned_in = 0;
-        game.territory_account = territory_account.key();
-        game.player_account = player_account.key();
+        init_game(
+            game,
+            ctx.accounts.creator.key(),
+            ctx.accounts.board.key(),
+            player_account.key(),
+            config,
+            Clock::get()?.unix_timestamp,
+        );
+        game.lobby = true;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.game = game.key();
+        vault.protocol_fee_bps = ctx.accounts.protocol_config.protocol_fee_bps;
+        vault.bump = ctx.bumps.vault;
+        deposit_entry_fee(
+            game,
+            vault,
+            &ctx.accounts.creator,
+            StakeTokenAccounts {
+                holder: ctx.accounts.creator_token_account.as_ref(),
+                vault: ctx.accounts.vault_token_account.as_ref(),
+                token_program: ctx.accounts.token_program.as_ref(),
+            },
+            &ctx.accounts.system_program,
+        )?;
 
-        // Initialize territory account
-        territory_account.territories = territories;
-        territory_account.continents = continents;
-        territory_account.game = game.key();
+        // Initialize board; larger maps continue with `load_map`
+        board.game = game.key();
+        board.append_map(&territories, &continents)?;
 
         // Initialize player account with color enum
         let color_enum = string_to_color(&color)?;
-        player_account.players = vec![PlayerState {
-            pubkey: ctx.accounts.creator.key(),
-            color_id: color_enum as u8,
-            cards: Vec::new(),
-            conquered_territory_this_turn: false,
-        }];
+        player_account.players = vec![PlayerState::new(
+            ctx.accounts.creator.key(),
+            color_enum as u8,
+            team_id,
+        )];
         player_account.game = game.key();
 
+        let profile = &mut ctx.accounts.creator_profile;
+        profile.player = ctx.accounts.creator.key();
+        profile.bump = ctx.bumps.creator_profile;
+
+        open_listing(
+            &mut ctx.accounts.listing,
+            ctx.bumps.listing,
+            game,
+            board,
+            player_account.players.len(),
+        );
+
+        Ok(())
+    }
+
+    // Ends a game nobody has moved in for `Game::ABANDONED_AFTER`. The pot then goes
+    // back to the players still in it, with no protocol fee.
+    pub fn abandon_game(ctx: Context<AbandonGame>) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        require!(
+            game.state == GameState::InProgress,
+            ErrorCode::GameNotInProgress
+        );
+        require!(
+            game.idle_for(
+                ctx.accounts.protocol_config.resumed_at,
+                Clock::get()?.unix_timestamp
+            ) >= Game::ABANDONED_AFTER,
+            ErrorCode::GameNotAbandoned
+        );
+
+        game.state = GameState::Abandoned;
+        ctx.accounts
+            .listing
+            .sync(game, ctx.accounts.player_account.players.len());
         Ok(())
     }
 
-    pub fn join_game(ctx: Context<JoinGame>, color: String) -> Result<()> {
+    pub fn load_map(
+        ctx: Context<LoadMap>,
+        territories: Vec<Territory>,
+        continents: Vec<Continent>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.game.state == GameState::Setup,
+            ErrorCode::GameAlreadyStarted
+        );
+
+        let board = &mut ctx.accounts.board.load_mut()?;
+        board.append_map(&territories, &continents)?;
+        ctx.accounts.listing.map_hash = board.map_hash();
+        Ok(())
+    }
+
+    pub fn join_game(ctx: Context<JoinGame>, color: String, team_id: Option<u8>) -> Result<()> {
         let game = &mut ctx.accounts.game;
         let player_account = &mut ctx.accounts.player_account;
 
@@ -76,10 +269,16 @@ pub mod risk_game {
             ErrorCode::GameAlreadyStarted
         );
         require!(
-            player_account.players.len() < 6,
+            player_account.players.len() < game.config.rules.max_players as usize,
             ErrorCode::MaxPlayersReached
         );
 
+        validate_team(&game.config.rules, team_id)?;
+        require!(
+            game.config.mission_dealer != Some(ctx.accounts.player.key()),
+            ErrorCode::DealerCannotPlay
+        );
+
         let color_enum = string_to_color(&color)?;
         let color_id = color_enum as u8;
 
@@ -92,19 +291,137 @@ pub mod risk_game {
             ErrorCode::ColorAlreadyTaken
         );
 
-        player_account.players.push(PlayerState {
-            pubkey: ctx.accounts.player.key(),
+        player_account.players.push(PlayerState::new(
+            ctx.accounts.player.key(),
             color_id,
-            cards: Vec::new(),
-            conquered_territory_this_turn: false,
-        });
+            team_id,
+        ));
+        let profile = &mut ctx.accounts.player_profile;
+        profile.player = ctx.accounts.player.key();
+        profile.bump = ctx.bumps.player_profile;
 
-        if player_account.players.len() >= 2 {
+        deposit_entry_fee(
+            game,
+            &mut ctx.accounts.vault,
+            &ctx.accounts.player,
+            StakeTokenAccounts {
+                holder: ctx.accounts.player_token_account.as_ref(),
+                vault: ctx.accounts.vault_token_account.as_ref(),
+                token_program: ctx.accounts.token_program.as_ref(),
+            },
+            &ctx.accounts.system_program,
+        )?;
+
+        if ready_to_start(game, &player_account.players) {
             game.state = GameState::InProgress;
+            game.turn_started_at = Clock::get()?.unix_timestamp;
         }
+        ctx.accounts
+            .listing
+            .sync(game, player_account.players.len());
         Ok(())
     }
 
+    // Frees the seat and color and refunds the entry fee; shrinking the player account
+    // refunds the slot's rent
+    pub fn leave_game(ctx: Context<LeaveGame>) -> Result<()> {
+        require!(
+            ctx.accounts.game.state == GameState::Setup,
+            ErrorCode::GameAlreadyStarted
+        );
+
+        let players = &mut ctx.accounts.player_account.players;
+        let seat = players
+            .iter()
+            .position(|p| p.pubkey == ctx.accounts.player.key())
+            .ok_or(ErrorCode::PlayerNotFound)?;
+        players.remove(seat);
+        ctx.accounts.listing.sync(&ctx.accounts.game, players.len());
+
+        refund_entry_fee(
+            &ctx.accounts.game,
+            &mut ctx.accounts.vault,
+            &ctx.accounts.player.to_account_info(),
+            StakeTokenAccounts {
+                holder: ctx.accounts.player_token_account.as_ref(),
+                vault: ctx.accounts.vault_token_account.as_ref(),
+                token_program: ctx.accounts.token_program.as_ref(),
+            },
+        )
+    }
+
+    // Joined players are passed in seat order as remaining accounts so each gets back
+    // their entry fee and the rent they paid for their slot; everything else goes to the
+    // creator. Token-staked games pass each player's token account after their wallet.
+    pub fn cancel_game<'info>(ctx: Context<'_, '_, 'info, 'info, CancelGame<'info>>) -> Result<()> {
+        let game = &ctx.accounts.game;
+        require!(
+            game.state == GameState::Setup,
+            ErrorCode::GameAlreadyStarted
+        );
+
+        let players = &ctx.accounts.player_account.players;
+        let accounts_per_player = if game.config.stake_mint.is_some() {
+            2
+        } else {
+            1
+        };
+        require!(
+            ctx.remaining_accounts.len() == (players.len() - 1) * accounts_per_player,
+            ErrorCode::PlayerNotFound
+        );
+
+        let rent = Rent::get()?;
+        for (seat, accounts) in (1..).zip(ctx.remaining_accounts.chunks(accounts_per_player)) {
+            let player = &accounts[0];
+            require_keys_eq!(
+                player.key(),
+                players[seat].pubkey,
+                ErrorCode::PlayerNotFound
+            );
+            let slot_rent = player_slot_rent(&rent, seat, &game.config.rules);
+            ctx.accounts.player_account.sub_lamports(slot_rent)?;
+            player.add_lamports(slot_rent)?;
+
+            let holder = accounts
+                .get(1)
+                .map(Account::<TokenAccount>::try_from)
+                .transpose()?;
+            if let Some(holder) = &holder {
+                require_keys_eq!(holder.owner, player.key(), ErrorCode::StakeAccountMissing);
+            }
+            refund_entry_fee(
+                game,
+                &mut ctx.accounts.vault,
+                player,
+                StakeTokenAccounts {
+                    holder: holder.as_ref(),
+                    vault: ctx.accounts.vault_token_account.as_ref(),
+                    token_program: ctx.accounts.token_program.as_ref(),
+                },
+            )?;
+        }
+
+        let creator = ctx.accounts.creator.to_account_info();
+        refund_entry_fee(
+            game,
+            &mut ctx.accounts.vault,
+            &creator,
+            StakeTokenAccounts {
+                holder: ctx.accounts.creator_token_account.as_ref(),
+                vault: ctx.accounts.vault_token_account.as_ref(),
+                token_program: ctx.accounts.token_program.as_ref(),
+            },
+        )?;
+        close_vault_token_account(
+            game,
+            &ctx.accounts.vault,
+            &creator,
+            ctx.accounts.vault_token_account.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+        )
+    }
+
     pub fn start_turn(ctx: Context<MakeMove>) -> Result<()> {
         let game = &mut ctx.accounts.game;
         require!(
@@ -119,12 +436,23 @@ pub mod risk_game {
             game.current_phase == TurnPhase::Reinforcement,
             ErrorCode::InvalidPhase
         );
+        if game.config.victory_mode == VictoryMode::Capital {
+            require!(
+                ctx.accounts
+                    .player_account
+                    .players
+                    .iter()
+                    .all(|p| p.capital.is_some()),
+                ErrorCode::CapitalsNotSelected
+            );
+        }
 
         let reinforcements = calculate_reinforcements(
-            &ctx.accounts.territory_account,
+            &*ctx.accounts.board.load()?,
             &ctx.accounts.player_account,
             &ctx.accounts.player.key(),
-        );
+            &game.config.rules,
+        )?;
         msg!("Player receives {} reinforcements", reinforcements);
 
         game.pending_reinforcements = Some(reinforcements);
@@ -154,20 +482,12 @@ pub mod risk_game {
             .pending_reinforcements
             .ok_or(ErrorCode::NoReinforcements)?;
 
-        // Validate total troops being placed matches reinforcements
-        let total_troops: u8 = placements.iter().map(|p| p.troops).sum();
-        require!(total_troops == reinforcements, ErrorCode::InvalidTroopCount);
-
-        // Place troops
-        for placement in placements {
-            let territory =
-                &mut ctx.accounts.territory_account.territories[placement.territory_id as usize];
-            require!(
-                territory.owner == Some(ctx.accounts.player.key()),
-                ErrorCode::NotTerritoryOwner
-            );
-            territory.troops += placement.troops;
-        }
+        place_troops(
+            &mut *ctx.accounts.board.load_mut()?,
+            &ctx.accounts.player.key(),
+            placements,
+            reinforcements,
+        )?;
 
         // Clear pending reinforcements and move to attack phase
         game.pending_reinforcements = None;
@@ -176,6 +496,8 @@ pub mod risk_game {
         Ok(())
     }
 
+    // Attacks on a territory another player owns must pass the pair's treaty PDA (see
+    // `treaty_parties`) in the remaining accounts, even if no truce was ever proposed
     pub fn attack(
         ctx: Context<MakeMove>,
         from_territory: u8,
@@ -196,32 +518,55 @@ pub mod risk_game {
             ErrorCode::InvalidPhase
         );
 
-        // Use territory program to validate and update territories
-        let territory_program = ctx.accounts.territory_program.to_account_info();
-        let territory_state = ctx.accounts.territory_state.to_account_info();
-        let authority = ctx.accounts.player.to_account_info();
+        let board = &mut ctx.accounts.board.load_mut()?;
 
         // Get territory information
-        let from_territory_ref = &ctx.accounts.territory_state.territories[from_territory as usize];
+        let from_territory_ref = *board.territory(from_territory)?;
         require!(
-            from_territory_ref.owner == Some(ctx.accounts.player.key()),
+            from_territory_ref.owner() == Some(ctx.accounts.player.key()),
             ErrorCode::NotTerritoryOwner
         );
         require!(
-            from_territory_ref.troops > attacking_dice,
+            from_territory_ref.troops > u16::from(attacking_dice),
             ErrorCode::InsufficientTroops
         );
-        require!(attacking_dice <= 3, ErrorCode::InvalidDiceCount);
+        require!(
+            (1..=game.config.rules.max_attack_dice).contains(&attacking_dice),
+            ErrorCode::InvalidDiceCount
+        );
 
-        let to_territory_ref = &ctx.accounts.territory_state.territories[to_territory as usize];
+        let to_territory_ref = *board.territory(to_territory)?;
         require!(
-            to_territory_ref.owner != Some(ctx.accounts.player.key()),
+            to_territory_ref.owner() != Some(ctx.accounts.player.key()),
             ErrorCode::CannotAttackOwnTerritory
         );
+        if let Some(defender) = to_territory_ref.owner() {
+            require!(
+                game.config.rules.team_attacks
+                    || !are_allies(
+                        &ctx.accounts.player_account.players,
+                        &ctx.accounts.player.key(),
+                        &defender,
+                    ),
+                ErrorCode::CannotAttackTeammate
+            );
+            require!(
+                !is_truce_active(
+                    game,
+                    &ctx.accounts.player.key(),
+                    &defender,
+                    ctx.remaining_accounts,
+                )?,
+                ErrorCode::TruceActive
+            );
+        }
 
         // Roll dice and resolve combat
         let attacker_dice = roll_dice(attacking_dice);
-        let defender_dice = roll_dice(std::cmp::min(2, to_territory_ref.troops));
+        let defender_dice = roll_dice(std::cmp::min(
+            u16::from(game.config.rules.max_defense_dice),
+            to_territory_ref.troops,
+        ) as u8);
 
         game.last_attack_dice = Some(AttackDice {
             attacker: attacker_dice.clone(),
@@ -230,28 +575,46 @@ pub mod risk_game {
 
         let (attacker_losses, defender_losses) = resolve_combat(&attacker_dice, &defender_dice);
 
-        // Update territories using CPI
-        territory::cpi_interface::update_territory(
-            territory_program.clone(),
-            territory_state.clone(),
-            authority.clone(),
-            from_territory,
-            Some(ctx.accounts.player.key()),
-            from_territory_ref.troops - attacker_losses,
-        )?;
+        let profile = &mut ctx.accounts.player_profile;
+        profile.dice_rolled = profile
+            .dice_rolled
+            .saturating_add(u64::from(attacking_dice));
 
-        let defending_territory =
-            &mut ctx.accounts.territory_state.territories[to_territory as usize];
+        // Update only the two territories involved
+        board.territory_mut(from_territory)?.troops = from_territory_ref
+            .troops
+            .checked_sub(attacker_losses)
+            .ok_or(ErrorCode::TroopOverflow)?;
+
+        let defending_territory = board.territory_mut(to_territory)?;
         if defending_territory.troops <= defender_losses {
             // Territory conquered
-            territory::cpi_interface::update_territory(
-                territory_program.clone(),
-                territory_state.clone(),
-                authority.clone(),
+            defending_territory.set_owner(Some(ctx.accounts.player.key()));
+            defending_territory.troops = u16::from(attacking_dice)
+                .checked_sub(attacker_losses)
+                .ok_or(ErrorCode::TroopOverflow)?;
+
+            if let Some(owner) = capture_capital(
+                &mut ctx.accounts.player_account.players,
                 to_territory,
                 Some(ctx.accounts.player.key()),
-                attacking_dice - attacker_losses,
-            )?;
+            ) {
+                msg!("Captured the capital of {}", owner);
+            }
+
+            let profile = &mut ctx.accounts.player_profile;
+            profile.territories_conquered = profile.territories_conquered.saturating_add(1);
+
+            if let Some(defender) = to_territory_ref.owner() {
+                if knock_out_if_wiped_out(
+                    board,
+                    &mut ctx.accounts.player_account.players,
+                    &defender,
+                ) {
+                    profile.eliminations = profile.eliminations.saturating_add(1);
+                    msg!("Player {} eliminated", defender);
+                }
+            }
 
             // Update player state using CPI
             let player_program = ctx.accounts.player_program.to_account_info();
@@ -259,29 +622,30 @@ pub mod risk_game {
 
             player::cpi_interface::set_conquered_territory(
                 player_program,
+                ctx.accounts.protocol_config.to_account_info(),
                 player_state,
-                authority.clone(),
+                ctx.accounts.player.to_account_info(),
                 ctx.accounts.player.key(),
                 true,
             )?;
         } else {
             // Territory not conquered
-            territory::cpi_interface::update_territory(
-                territory_program.clone(),
-                territory_state.clone(),
-                authority.clone(),
-                to_territory,
-                defending_territory.owner,
-                defending_territory.troops - defender_losses,
-            )?;
+            defending_territory.troops -= defender_losses;
         }
 
-        check_victory_condition(game, &ctx.accounts.territory_state);
+        check_victory_condition(game, board, &ctx.accounts.player_account.players);
+        ctx.accounts
+            .listing
+            .sync(game, ctx.accounts.player_account.players.len());
         Ok(())
     }
 
     pub fn end_phase(ctx: Context<MakeMove>) -> Result<()> {
         let game = &mut ctx.accounts.game;
+        require!(
+            game.state == GameState::InProgress,
+            ErrorCode::GameNotInProgress
+        );
         require!(
             game.current_player == ctx.accounts.player.key(),
             ErrorCode::NotPlayerTurn
@@ -305,14 +669,25 @@ pub mod risk_game {
                     .unwrap();
 
                 if player.conquered_territory_this_turn {
-                    require!(player.cards.len() < 5, ErrorCode::CardLimitReached);
+                    require!(
+                        player.cards.len() < game.config.rules.card_limit as usize,
+                        ErrorCode::CardLimitReached
+                    );
                     player.cards.push(generate_risk_card());
                     player.conquered_territory_this_turn = false;
                 }
 
-                // Move to next player's turn
+                // Move to next player's turn; the turn limit may end the game here
                 game.current_phase = TurnPhase::Reinforcement;
-                next_turn(game, &ctx.accounts.player_account);
+                next_turn(
+                    game,
+                    &ctx.accounts.player_account.players,
+                    &*ctx.accounts.board.load()?,
+                    Clock::get()?.unix_timestamp,
+                );
+                ctx.accounts
+                    .listing
+                    .sync(game, ctx.accounts.player_account.players.len());
             }
         }
 
@@ -334,15 +709,6 @@ pub mod risk_game {
             ErrorCode::InvalidPhase
         );
 
-        let bonus_armies = match game.cards_sets_turned_in {
-            0 => 4,
-            1 => 6,
-            2 => 8,
-            3 => 10,
-            4 => 12,
-            _ => 15,
-        };
-
         let player = ctx
             .accounts
             .player_account
@@ -369,6 +735,13 @@ pub mod risk_game {
         // Validate card set
         require!(is_valid_card_set(&cards), ErrorCode::InvalidCardSet);
 
+        let bonus_armies = card_set_bonus(
+            &game.config.card_bonus_schedule,
+            game.cards_sets_turned_in,
+            &cards,
+        );
+        let bonus_armies = u16::try_from(bonus_armies).map_err(|_| ErrorCode::TroopOverflow)?;
+
         // Remove cards from player's hand (remove from highest index first to maintain validity)
         let mut sorted_indices = card_indices.clone();
         sorted_indices.sort_by(|a, b| b.cmp(a));
@@ -377,34 +750,39 @@ pub mod risk_game {
         }
 
         // Award bonus armies
-        game.pending_reinforcements = Some(game.pending_reinforcements.unwrap_or(0) + bonus_armies);
-        game.cards_sets_turned_in += 1;
+        let pending = game
+            .pending_reinforcements
+            .unwrap_or(0)
+            .checked_add(bonus_armies)
+            .ok_or(ErrorCode::TroopOverflow)?;
+        game.pending_reinforcements = Some(pending);
+        game.cards_sets_turned_in = game.cards_sets_turned_in.saturating_add(1);
 
         Ok(())
     }
 
     pub fn distribute_initial_territories(ctx: Context<MakeMove>) -> Result<()> {
         let game = &mut ctx.accounts.game;
-        let territory_account = &mut ctx.accounts.territory_account;
+        let board = &mut ctx.accounts.board.load_mut()?;
         let player_account = &ctx.accounts.player_account;
 
         require!(
             game.state == GameState::InProgress,
             ErrorCode::GameNotInProgress
         );
+        board.validate_map()?;
 
-        distribute_territories(territory_account, player_account);
-        Ok(())
+        deal(game, board, &player_account.players)
     }
 
     pub fn fortify(
         ctx: Context<MakeMove>,
         from_territory: u8,
         to_territory: u8,
-        troops: u8,
+        troops: u16,
     ) -> Result<()> {
         let game = &mut ctx.accounts.game;
-        let territory_account = &mut ctx.accounts.territory_account;
+        let board = &mut ctx.accounts.board.load_mut()?;
 
         // Basic state validations
         require!(
@@ -420,245 +798,3546 @@ pub mod risk_game {
             ErrorCode::InvalidPhase
         );
 
-        // Validate territory ownership and troops
-        let from_territory_ref = &territory_account.territories[from_territory as usize];
+        let player = ctx.accounts.player.key();
+        let allies = allies_of(&ctx.accounts.player_account.players, &player);
+        apply_fortify_move(
+            game,
+            board,
+            &player,
+            &allies,
+            &FortifyMove {
+                from_territory,
+                to_territory,
+                troops,
+            },
+        )
+    }
+
+    pub fn fortify_many(ctx: Context<MakeMove>, moves: Vec<FortifyMove>) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        let board = &mut ctx.accounts.board.load_mut()?;
+
+        require!(
+            game.state == GameState::InProgress,
+            ErrorCode::GameNotInProgress
+        );
+        require!(
+            game.current_player == ctx.accounts.player.key(),
+            ErrorCode::NotPlayerTurn
+        );
+        require!(
+            game.current_phase == TurnPhase::Fortification,
+            ErrorCode::InvalidPhase
+        );
+        require!(!moves.is_empty(), ErrorCode::InvalidTroopCount);
+
+        // Moves apply in order, each validated against the board left by the previous one.
+        // Any failure aborts the transaction, so either every move lands or none do.
+        let player = ctx.accounts.player.key();
+        let allies = allies_of(&ctx.accounts.player_account.players, &player);
+        for fortify_move in coalesce_fortify_moves(moves)? {
+            apply_fortify_move(game, board, &player, &allies, &fortify_move)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn select_capital(ctx: Context<MakeMove>, territory_id: u8) -> Result<()> {
+        let game = &ctx.accounts.game;
         require!(
-            from_territory_ref.owner == Some(ctx.accounts.player.key()),
-            ErrorCode::NotTerritoryOwner
+            game.state == GameState::InProgress,
+            ErrorCode::GameNotInProgress
         );
         require!(
-            from_territory_ref.troops > troops,
-            ErrorCode::InsufficientTroops
+            game.config.victory_mode == VictoryMode::Capital,
+            ErrorCode::InvalidVictoryMode
+        );
+        // Capitals are chosen after distribution and before anyone takes a turn
+        require!(
+            game.dealt
+                && game.turn == 1
+                && game.current_phase == TurnPhase::Reinforcement
+                && game.pending_reinforcements.is_none(),
+            ErrorCode::CapitalSelectionClosed
         );
 
-        let to_territory_ref = &territory_account.territories[to_territory as usize];
+        let player = ctx.accounts.player.key();
+        let board = ctx.accounts.board.load()?;
         require!(
-            to_territory_ref.owner == Some(ctx.accounts.player.key()),
+            board.territory(territory_id)?.owner() == Some(player),
             ErrorCode::NotTerritoryOwner
         );
 
-        // Validate connectivity
-        let are_connected = are_territories_connected(
-            territory_account,
-            from_territory,
-            to_territory,
-            &ctx.accounts.player.key(),
+        let player_state = ctx
+            .accounts
+            .player_account
+            .players
+            .iter_mut()
+            .find(|p| p.pubkey == player)
+            .ok_or(ErrorCode::PlayerNotFound)?;
+        require!(
+            player_state.capital.is_none(),
+            ErrorCode::CapitalAlreadySelected
         );
-        require!(are_connected, ErrorCode::TerritoriesNotConnected);
-
-        // Move troops
-        territory_account.territories[from_territory as usize].troops -= troops;
-        territory_account.territories[to_territory as usize].troops += troops;
 
+        player_state.capital = Some(territory_id);
+        player_state.capital_holder = Some(player);
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-pub struct InitializeGame<'info> {
-    #[account(init, payer = creator, space = 8 + Game::SPACE)]
-    pub game: Account<'info, Game>,
-    #[account(init, payer = creator, space = 8 + TerritoryAccount::SPACE)]
-    pub territory_account: Account<'info, TerritoryAccount>,
-    #[account(init, payer = creator, space = 8 + PlayerAccount::SPACE)]
-    pub player_account: Account<'info, PlayerAccount>,
-    #[account(mut)]
-    pub creator: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    // Missions are dealt while the lobby fills; the game starts once every seat has one
+    pub fn commit_mission(
+        ctx: Context<CommitMission>,
+        player: Pubkey,
+        commitment: [u8; 32],
+    ) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        let player_account = &mut ctx.accounts.player_account;
+        require!(
+            game.config.victory_mode == VictoryMode::SecretMission,
+            ErrorCode::InvalidVictoryMode
+        );
+        require!(
+            game.state == GameState::Setup,
+            ErrorCode::MissionCommitmentClosed
+        );
 
-#[derive(Accounts)]
-pub struct JoinGame<'info> {
-    #[account(mut)]
-    pub game: Account<'info, Game>,
-    #[account(mut, constraint = player_account.game == game.key())]
-    pub player_account: Account<'info, PlayerAccount>,
-    pub player: Signer<'info>,
-}
+        let player_state = player_account
+            .players
+            .iter_mut()
+            .find(|p| p.pubkey == player)
+            .ok_or(ErrorCode::PlayerNotFound)?;
+        require!(
+            player_state.mission_commitment.is_none(),
+            ErrorCode::MissionAlreadyCommitted
+        );
+        player_state.mission_commitment = Some(commitment);
 
-#[derive(Accounts)]
-pub struct MakeMove<'info> {
-    #[account(mut)]
-    pub game: Account<'info, Game>,
-    #[account(mut, constraint = territory_account.game == game.key())]
-    pub territory_account: Account<'info, TerritoryAccount>,
-    #[account(mut, constraint = player_account.game == game.key())]
-    pub player_account: Account<'info, PlayerAccount>,
-    pub player: Signer<'info>,
-    pub territory_program: Program<'info, territory::program::Territory>,
-    #[account(mut)]
-    pub territory_state: Account<'info, territory::TerritoryState>,
-    pub player_program: Program<'info, player::program::Player>,
-    #[account(mut)]
-    pub player_state: Account<'info, player::PlayerState>,
-}
+        if ready_to_start(game, &player_account.players) {
+            game.state = GameState::InProgress;
+            game.turn_started_at = Clock::get()?.unix_timestamp;
+        }
+        ctx.accounts
+            .listing
+            .sync(game, player_account.players.len());
+        Ok(())
+    }
 
-#[account]
-pub struct Game {
-    pub current_player: Pubkey,
-    pub turn: u8,
-    pub state: GameState,
-    pub current_phase: TurnPhase,
-    pub cards_sets_turned_in: u8,
-    pub last_attack_dice: Option<AttackDice>,
-    pub pending_reinforcements: Option<u8>,
-    pub territory_account: Pubkey,
-    pub player_account: Pubkey,
-}
+    pub fn claim_mission_victory(
+        ctx: Context<MakeMove>,
+        mission: Mission,
+        salt: [u8; 32],
+    ) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        require!(
+            game.state == GameState::InProgress,
+            ErrorCode::GameNotInProgress
+        );
+        require!(
+            game.config.victory_mode == VictoryMode::SecretMission,
+            ErrorCode::InvalidVictoryMode
+        );
 
-#[account]
-pub struct TerritoryAccount {
-    pub territories: Vec<Territory>,
-    pub continents: Vec<Continent>,
+        let claimant = ctx.accounts.player.key();
+        let player_state = ctx
+            .accounts
+            .player_account
+            .players
+            .iter()
+            .find(|p| p.pubkey == claimant)
+            .ok_or(ErrorCode::PlayerNotFound)?;
+
+        // Reveal: the mission and salt must hash to what the dealer committed
+        let commitment = player_state
+            .mission_commitment
+            .ok_or(ErrorCode::MissionNotCommitted)?;
+        require!(
+            mission_commitment(&claimant, &mission, &salt)? == commitment,
+            ErrorCode::MissionMismatch
+        );
+
+        let board = ctx.accounts.board.load()?;
+        mission.validate(&board, ctx.accounts.player_account.players.len())?;
+        require!(
+            is_mission_complete(&mission, &claimant, &board, &ctx.accounts.player_account),
+            ErrorCode::MissionNotComplete
+        );
+
+        game.state = GameState::Completed;
+        game.winner = Some(claimant);
+        ctx.accounts
+            .listing
+            .sync(game, ctx.accounts.player_account.players.len());
+        Ok(())
+    }
+
+    // Territories go neutral, or to `heir` if one is named
+    pub fn resign(ctx: Context<Resign>, heir: Option<Pubkey>) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        let board = &mut ctx.accounts.board.load_mut()?;
+        let player_account = &mut ctx.accounts.player_account;
+        let player = ctx.accounts.player.key();
+
+        require!(
+            game.state == GameState::InProgress,
+            ErrorCode::GameNotInProgress
+        );
+        if let Some(heir) = heir {
+            require!(
+                heir != player
+                    && player_account
+                        .players
+                        .iter()
+                        .any(|p| p.pubkey == heir && !p.eliminated),
+                ErrorCode::InvalidHeir
+            );
+        }
+
+        forfeit(
+            game,
+            board,
+            &mut player_account.players,
+            player,
+            heir,
+            Clock::get()?.unix_timestamp,
+        )?;
+        ctx.accounts
+            .listing
+            .sync(game, player_account.players.len());
+        Ok(())
+    }
+
+    pub fn propose_truce(ctx: Context<ProposeTruce>, target: Pubkey, turns: u32) -> Result<()> {
+        let game = &ctx.accounts.game;
+        let treaty = &mut ctx.accounts.treaty;
+        let proposer = ctx.accounts.proposer.key();
+
+        require!(
+            game.state == GameState::InProgress,
+            ErrorCode::GameNotInProgress
+        );
+        require!(proposer != target && turns > 0, ErrorCode::InvalidTruce);
+        for party in [proposer, target] {
+            require!(
+                ctx.accounts
+                    .player_account
+                    .players
+                    .iter()
+                    .any(|p| p.pubkey == party),
+                ErrorCode::PlayerNotFound
+            );
+        }
+        // The pair's treaty account is reused once the previous truce has lapsed
+        require!(!treaty.is_active(game.turn), ErrorCode::TruceActive);
+
+        treaty.game = game.key();
+        treaty.proposer = proposer;
+        treaty.target = target;
+        treaty.turns = turns;
+        treaty.expires_at_turn = 0;
+        treaty.status = TreatyStatus::Proposed;
+        treaty.broken_by = None;
+        treaty.bump = ctx.bumps.treaty;
+        Ok(())
+    }
+
+    pub fn accept_truce(ctx: Context<RespondTruce>) -> Result<()> {
+        let game = &ctx.accounts.game;
+        let treaty = &mut ctx.accounts.treaty;
+
+        require!(
+            game.state == GameState::InProgress,
+            ErrorCode::GameNotInProgress
+        );
+        require!(
+            treaty.status == TreatyStatus::Proposed,
+            ErrorCode::InvalidTruce
+        );
+        require!(
+            treaty.target == ctx.accounts.player.key(),
+            ErrorCode::NotTreatyParty
+        );
+
+        treaty.status = TreatyStatus::Active;
+        treaty.expires_at_turn = game
+            .turn
+            .checked_add(treaty.turns)
+            .ok_or(ErrorCode::InvalidTruce)?;
+        Ok(())
+    }
+
+    pub fn break_truce(ctx: Context<RespondTruce>) -> Result<()> {
+        let game = &ctx.accounts.game;
+        let treaty = &mut ctx.accounts.treaty;
+        let player = ctx.accounts.player.key();
+
+        require!(
+            game.state == GameState::InProgress,
+            ErrorCode::GameNotInProgress
+        );
+        require!(
+            treaty.proposer == player || treaty.target == player,
+            ErrorCode::NotTreatyParty
+        );
+        require!(treaty.is_active(game.turn), ErrorCode::InvalidTruce);
+
+        treaty.status = TreatyStatus::Broken;
+        treaty.broken_by = Some(player);
+
+        emit!(TruceBroken {
+            game: game.key(),
+            treaty: treaty.key(),
+            broken_by: player,
+            turn: game.turn,
+        });
+        Ok(())
+    }
+
+    // Credits a finished game to every seat's profile, passed in seat order as
+    // remaining accounts, and rates the result in the game's rating pool. Runs once per game.
+    pub fn record_game_stats<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RecordGameStats<'info>>,
+    ) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        require!(
+            game.state == GameState::Completed,
+            ErrorCode::GameNotFinished
+        );
+        require!(!game.stats_recorded, ErrorCode::StatsAlreadyRecorded);
+
+        let players = &ctx.accounts.player_account.players;
+        require!(
+            ctx.remaining_accounts.len() == players.len(),
+            ErrorCode::PlayerNotFound
+        );
+
+        let mut profiles = Vec::with_capacity(players.len());
+        for (player, profile_info) in players.iter().zip(ctx.remaining_accounts) {
+            let profile = Account::<PlayerProfile>::try_from(profile_info)?;
+            require_keys_eq!(profile.player, player.pubkey, ErrorCode::PlayerNotFound);
+            profiles.push(profile);
+        }
+
+        // Winning teammates share first place; everyone else places by the final ranking
+        let ranking = final_ranking(game, &*ctx.accounts.board.load()?, players);
+        let places: Vec<usize> = players
+            .iter()
+            .map(|p| {
+                if game.winning_team.is_some() && game.winning_team == p.team_id {
+                    0
+                } else {
+                    ranking
+                        .iter()
+                        .position(|r| *r == p.pubkey)
+                        .unwrap_or(ranking.len())
+                }
+            })
+            .collect();
+
+        let pool = game
+            .config
+            .rating_pool(&ctx.accounts.board.load()?.map_hash());
+        let ratings: Vec<i64> = profiles.iter().map(|p| p.rating(&pool)).collect();
+        let changes = elo_changes(&ratings, &places);
+
+        for ((player, profile), change) in players.iter().zip(&mut profiles).zip(changes) {
+            let won = game.winner == Some(player.pubkey)
+                || (game.winning_team.is_some() && game.winning_team == player.team_id);
+            profile.record_game(player.color_id, won);
+            profile.rate(pool, change);
+            profile.exit(&crate::ID)?;
+        }
+
+        game.stats_recorded = true;
+        Ok(())
+    }
+
+    // Anyone may settle a payee's share, so the creator can pay everyone out before closing
+    pub fn claim_winnings(ctx: Context<ClaimWinnings>) -> Result<()> {
+        let game = &ctx.accounts.game;
+        let vault = &mut ctx.accounts.vault;
+        let players = &ctx.accounts.player_account.players;
+        require!(game.is_finished(), ErrorCode::GameNotFinished);
+
+        // The protocol fee comes off the top, paid to the treasury with the first claim
+        let protocol_fee = vault.protocol_fee(game);
+        if protocol_fee > 0 && !vault.fee_collected {
+            vault.fee_collected = true;
+            vault.paid_out = vault
+                .paid_out
+                .checked_add(protocol_fee)
+                .ok_or(ErrorCode::PotOverflow)?;
+            withdraw_stake(
+                game,
+                vault,
+                protocol_fee,
+                &ctx.accounts.treasury.to_account_info(),
+                StakeTokenAccounts {
+                    holder: ctx.accounts.treasury_token_account.as_ref(),
+                    vault: ctx.accounts.vault_token_account.as_ref(),
+                    token_program: ctx.accounts.token_program.as_ref(),
+                },
+            )?;
+        }
+
+        let prize_pool = vault.pot - protocol_fee;
+        let (seat, share) = prize_shares(game, players, &*ctx.accounts.board.load()?, prize_pool)
+            .into_iter()
+            .find(|&(seat, _)| players[seat].pubkey == ctx.accounts.payee.key())
+            .ok_or(ErrorCode::NoWinnings)?;
+        require!(
+            vault.claimed_seats & (1 << seat) == 0,
+            ErrorCode::WinningsAlreadyClaimed
+        );
+
+        vault.claimed_seats |= 1 << seat;
+        vault.paid_out = vault
+            .paid_out
+            .checked_add(share)
+            .ok_or(ErrorCode::PotOverflow)?;
+        withdraw_stake(
+            game,
+            vault,
+            share,
+            &ctx.accounts.payee.to_account_info(),
+            StakeTokenAccounts {
+                holder: ctx.accounts.payee_token_account.as_ref(),
+                vault: ctx.accounts.vault_token_account.as_ref(),
+                token_program: ctx.accounts.token_program.as_ref(),
+            },
+        )
+    }
+
+    // Players who joined a lobby get back the rent for their slot, as in `cancel_game`:
+    // their wallets come first in the remaining accounts, in seat order after the
+    // creator's. Treaties are refunded to their proposers, passed as (treaty, proposer)
+    // pairs after them.
+    pub fn close_game<'info>(ctx: Context<'_, '_, 'info, 'info, CloseGame<'info>>) -> Result<()> {
+        let game = &ctx.accounts.game;
+        let now = Clock::get()?.unix_timestamp;
+        require!(game.is_finished(), ErrorCode::GameNotFinished);
+
+        let players = &ctx.accounts.player_account.players;
+        let joined = if game.lobby {
+            players.len().saturating_sub(1)
+        } else {
+            0
+        };
+        require!(
+            ctx.remaining_accounts.len() >= joined,
+            ErrorCode::PlayerNotFound
+        );
+        let (seat_wallets, treaty_accounts) = ctx.remaining_accounts.split_at(joined);
+        let rent = Rent::get()?;
+        for (seat, wallet) in (1..).zip(seat_wallets) {
+            require_keys_eq!(
+                wallet.key(),
+                players[seat].pubkey,
+                ErrorCode::PlayerNotFound
+            );
+            let slot_rent = player_slot_rent(&rent, seat, &game.config.rules);
+            ctx.accounts.player_account.sub_lamports(slot_rent)?;
+            wallet.add_lamports(slot_rent)?;
+        }
+
+        if let Some(game_result) = &mut ctx.accounts.game_result {
+            game_result.game = game.key();
+            game_result.creator = game.creator;
+            game_result.winner = game.winner;
+            game_result.winning_team = game.winning_team;
+            game_result.ranking = game.ranking.clone();
+            game_result.turns = game.turn;
+            game_result.abandoned = game.state != GameState::Completed;
+            game_result.closed_at = now;
+            game_result.bump = ctx.bumps.game_result;
+        }
+
+        for pair in treaty_accounts.chunks(2) {
+            let [treaty_info, proposer] = pair else {
+                return err!(ErrorCode::TreatyAccountMissing);
+            };
+            let treaty = Account::<Treaty>::try_from(treaty_info)?;
+            require_keys_eq!(treaty.game, game.key(), ErrorCode::InvalidTruce);
+            require_keys_eq!(treaty.proposer, proposer.key(), ErrorCode::NotTreatyParty);
+            treaty.close(proposer.clone())?;
+        }
+
+        if let (Some(player_state), Some(player_program)) =
+            (&ctx.accounts.player_state, &ctx.accounts.player_program)
+        {
+            player::cpi::close(CpiContext::new(
+                player_program.to_account_info(),
+                player::cpi::accounts::ClosePlayerState {
+                    protocol_config: ctx.accounts.protocol_config.to_account_info(),
+                    game: game.to_account_info(),
+                    player_state: player_state.to_account_info(),
+                    authority: ctx.accounts.creator.to_account_info(),
+                },
+            ))?;
+        }
+        if let (Some(territory_state), Some(territory_program)) = (
+            &ctx.accounts.territory_state,
+            &ctx.accounts.territory_program,
+        ) {
+            territory::cpi::close(CpiContext::new(
+                territory_program.to_account_info(),
+                territory::cpi::accounts::CloseTerritoryState {
+                    protocol_config: ctx.accounts.protocol_config.to_account_info(),
+                    game: game.to_account_info(),
+                    territory_state: territory_state.to_account_info(),
+                    authority: ctx.accounts.creator.to_account_info(),
+                },
+            ))?;
+        }
+
+        close_vault_token_account(
+            game,
+            &ctx.accounts.vault,
+            &ctx.accounts.creator.to_account_info(),
+            ctx.accounts.vault_token_account.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+        )
+    }
+
+    // Opens a matchmaking queue for games with `config` on the map in `template`, a
+    // board account the client allocates the same way as for `initialize_game`
+    pub fn create_queue(
+        ctx: Context<CreateQueue>,
+        config: GameConfig,
+        player_count: u8,
+        territories: Vec<Territory>,
+        continents: Vec<Continent>,
+    ) -> Result<()> {
+        validate_queue_config(&config, player_count)?;
+
+        let queue = &mut ctx.accounts.queue;
+        queue.creator = ctx.accounts.creator.key();
+        queue.config = config;
+        queue.player_count = player_count;
+        queue.template = ctx.accounts.template.key();
+        queue.entries = Vec::new();
+
+        let template = &mut ctx.accounts.template.load_init()?;
+        template.game = queue.key();
+        template.append_map(&territories, &continents)?;
+        template.validate_map()?;
+        queue.rating_pool = queue.config.rating_pool(&template.map_hash());
+        Ok(())
+    }
+
+    // Escrows the entry fee in the queue until the player is matched or leaves
+    pub fn enqueue(ctx: Context<Enqueue>, rating_band: u32) -> Result<()> {
+        let queue = &mut ctx.accounts.queue;
+        let player = ctx.accounts.player.key();
+        require!(
+            queue.entries.len() < MatchQueue::MAX_ENTRIES,
+            ErrorCode::QueueFull
+        );
+        require!(
+            !queue.entries.iter().any(|e| e.player == player),
+            ErrorCode::AlreadyQueued
+        );
+
+        let profile = &mut ctx.accounts.player_profile;
+        profile.player = player;
+        profile.bump = ctx.bumps.player_profile;
+
+        if let Some(entry_fee) = queue.config.entry_fee {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.player.to_account_info(),
+                        to: queue.to_account_info(),
+                    },
+                ),
+                entry_fee,
+            )?;
+        }
+
+        let rating = profile.rating(&queue.rating_pool);
+        queue.entries.push(QueueEntry {
+            player,
+            rating,
+            rating_band,
+            enqueued_at: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn leave_queue(ctx: Context<LeaveQueue>) -> Result<()> {
+        let queue = &mut ctx.accounts.queue;
+        remove_queue_entry(queue, &ctx.accounts.player.key())?;
+        refund_queue_entry(queue, &ctx.accounts.player.to_account_info())
+    }
+
+    // Anyone may return the stake of a player who has waited longer than
+    // `MatchQueue::ENTRY_TIMEOUT`
+    pub fn expire_queue_entry(ctx: Context<ExpireQueueEntry>) -> Result<()> {
+        let queue = &mut ctx.accounts.queue;
+        let entry = remove_queue_entry(queue, &ctx.accounts.player.key())?;
+        require!(
+            Clock::get()?.unix_timestamp - entry.enqueued_at >= MatchQueue::ENTRY_TIMEOUT,
+            ErrorCode::QueueEntryNotExpired
+        );
+        refund_queue_entry(queue, &ctx.accounts.player.to_account_info())
+    }
+
+    // Permissionless crank: seats the longest-waiting players who fit each other's rating
+    // bands, see `pick_match`, in a new game that starts immediately. Seating follows
+    // `seat_order`, so the cranker picks neither who plays nor who goes first. The cranker
+    // pays the game's rent and gets it back as its creator in `close_game`.
+    pub fn match_players(ctx: Context<MatchPlayers>) -> Result<()> {
+        let queue = &mut ctx.accounts.queue;
+        let mut picked = pick_match(&queue.entries, queue.player_count as usize)
+            .ok_or(ErrorCode::InvalidMatch)?;
+        let matched: Vec<QueueEntry> = picked.iter().map(|&i| queue.entries[i].clone()).collect();
+        let players = seat_order(&matched);
+        picked.sort_unstable();
+        for &i in picked.iter().rev() {
+            queue.entries.remove(i);
+        }
+
+        let game = &mut ctx.accounts.game;
+        let player_account = &mut ctx.accounts.player_account;
+        init_game(
+            game,
+            ctx.accounts.cranker.key(),
+            ctx.accounts.board.key(),
+            player_account.key(),
+            queue.config.clone(),
+            Clock::get()?.unix_timestamp,
+        );
+        game.state = GameState::InProgress;
+        game.current_player = players[0];
+
+        let board = &mut ctx.accounts.board.load_init()?;
+        board.game = game.key();
+        board.copy_map_from(&*ctx.accounts.template.load()?);
+
+        seat_players(player_account, game.key(), &players);
+        deal(game, board, &player_account.players)?;
+
+        // The escrowed stakes move from the queue to the game's vault
+        let vault = &mut ctx.accounts.vault;
+        vault.game = game.key();
+        vault.protocol_fee_bps = ctx.accounts.protocol_config.protocol_fee_bps;
+        vault.bump = ctx.bumps.vault;
+        if let Some(entry_fee) = game.config.entry_fee {
+            let pot = entry_fee
+                .checked_mul(players.len() as u64)
+                .ok_or(ErrorCode::PotOverflow)?;
+            queue.sub_lamports(pot)?;
+            vault.add_lamports(pot)?;
+            vault.pot = pot;
+        }
+
+        open_listing(
+            &mut ctx.accounts.listing,
+            ctx.bumps.listing,
+            game,
+            board,
+            players.len(),
+        );
+        msg!("Matched {} players into game {}", players.len(), game.key());
+        Ok(())
+    }
+
+    // Returns the queue's and template's rent once nobody is waiting
+    pub fn close_queue(ctx: Context<CloseQueue>) -> Result<()> {
+        require!(
+            ctx.accounts.queue.entries.is_empty(),
+            ErrorCode::QueueNotEmpty
+        );
+        Ok(())
+    }
+
+    // Every table plays `config` on the map in `template`, allocated like a game board.
+    // Stakes are held by the tournament, so the tables themselves are free to enter.
+    pub fn create_tournament(
+        ctx: Context<CreateTournament>,
+        config: GameConfig,
+        settings: TournamentSettings,
+        territories: Vec<Territory>,
+        continents: Vec<Continent>,
+    ) -> Result<()> {
+        validate_tournament(&config, &settings)?;
+
+        let tournament = &mut ctx.accounts.tournament;
+        tournament.organizer = ctx.accounts.organizer.key();
+        tournament.config = config;
+        tournament.settings = settings;
+        tournament.template = ctx.accounts.template.key();
+        tournament.state = TournamentState::Registration;
+        tournament.protocol_fee_bps = ctx.accounts.protocol_config.protocol_fee_bps;
+        tournament.last_activity_at = Clock::get()?.unix_timestamp;
+
+        let template = &mut ctx.accounts.template.load_init()?;
+        template.game = tournament.key();
+        template.append_map(&territories, &continents)?;
+        template.validate_map()
+    }
+
+    pub fn register_for_tournament(ctx: Context<RegisterForTournament>) -> Result<()> {
+        let tournament = &mut ctx.accounts.tournament;
+        let player = ctx.accounts.player.key();
+        require!(
+            tournament.state == TournamentState::Registration,
+            ErrorCode::TournamentAlreadyStarted
+        );
+        require!(
+            tournament.players.len() < tournament.settings.max_players as usize,
+            ErrorCode::MaxPlayersReached
+        );
+        require!(
+            !tournament.players.iter().any(|p| p.pubkey == player),
+            ErrorCode::AlreadyQueued
+        );
+
+        if let Some(entry_fee) = tournament.settings.entry_fee {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.player.to_account_info(),
+                        to: tournament.to_account_info(),
+                    },
+                ),
+                entry_fee,
+            )?;
+            tournament.pot = tournament
+                .pot
+                .checked_add(entry_fee)
+                .ok_or(ErrorCode::PotOverflow)?;
+        }
+
+        tournament.players.push(TournamentPlayer {
+            pubkey: player,
+            points: 0,
+            eliminated_in_round: None,
+            last_place: 0,
+        });
+        tournament.last_activity_at = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    // Hands a player's entry fee back while registration is still open
+    pub fn leave_tournament(ctx: Context<LeaveTournament>) -> Result<()> {
+        let tournament = &mut ctx.accounts.tournament;
+        require!(
+            tournament.state == TournamentState::Registration,
+            ErrorCode::TournamentAlreadyStarted
+        );
+        let index = tournament
+            .players
+            .iter()
+            .position(|p| p.pubkey == ctx.accounts.player.key())
+            .ok_or(ErrorCode::PlayerNotFound)?;
+        tournament.players.remove(index);
+
+        if let Some(entry_fee) = tournament.settings.entry_fee {
+            tournament.pot = tournament
+                .pot
+                .checked_sub(entry_fee)
+                .ok_or(ErrorCode::PotOverflow)?;
+            tournament.sub_lamports(entry_fee)?;
+            ctx.accounts.player.add_lamports(entry_fee)?;
+        }
+        tournament.last_activity_at = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    // The organizer can call off a tournament before it starts; once it has gone
+    // `Tournament::STALLED_AFTER` without progress anyone can. Entry fees are then
+    // refunded through `claim_tournament_prize`.
+    pub fn cancel_tournament(ctx: Context<CancelTournament>) -> Result<()> {
+        let tournament = &mut ctx.accounts.tournament;
+        require!(
+            tournament.state == TournamentState::Registration
+                || tournament.state == TournamentState::InProgress,
+            ErrorCode::TournamentFinished
+        );
+        let by_organizer = tournament.state == TournamentState::Registration
+            && ctx.accounts.signer.key() == tournament.organizer;
+        // A pause holds the stall clock too, see `set_paused`
+        let idle_since = tournament
+            .last_activity_at
+            .max(ctx.accounts.protocol_config.resumed_at);
+        let stalled =
+            Clock::get()?.unix_timestamp.saturating_sub(idle_since) >= Tournament::STALLED_AFTER;
+        require!(by_organizer || stalled, ErrorCode::TournamentNotStalled);
+
+        tournament.state = TournamentState::Cancelled;
+        Ok(())
+    }
+
+    // Closes registration or, once every table has reported, pairs the next round.
+    // Ends the tournament when a single player is left standing or the Swiss rounds
+    // have all been played.
+    pub fn start_round(ctx: Context<StartRound>) -> Result<()> {
+        advance_round(&mut ctx.accounts.tournament, Clock::get()?.unix_timestamp)
+    }
+
+    // Creates the game for one table of the current round. The organizer pays its rent
+    // and gets it back as the game's creator in `close_game`.
+    pub fn spawn_table(ctx: Context<SpawnTable>, table: u8) -> Result<()> {
+        let tournament = &mut ctx.accounts.tournament;
+        require!(
+            tournament.state == TournamentState::InProgress,
+            ErrorCode::TournamentNotInProgress
+        );
+        let table_index = table;
+        let table = tournament
+            .tables
+            .get_mut(table as usize)
+            .ok_or(ErrorCode::InvalidTable)?;
+        require!(
+            table.game == Pubkey::default() && !table.recorded,
+            ErrorCode::InvalidTable
+        );
+        table.game = ctx.accounts.game.key();
+        let seats = table.seats.clone();
+        let entrants: Vec<Pubkey> = seats
+            .iter()
+            .map(|&i| tournament.players[i as usize].pubkey)
+            .collect();
+        let players = table_seat_order(&tournament.key(), tournament.round, table_index, &entrants);
+        tournament.last_activity_at = Clock::get()?.unix_timestamp;
+
+        let game = &mut ctx.accounts.game;
+        let player_account = &mut ctx.accounts.player_account;
+        init_game(
+            game,
+            ctx.accounts.organizer.key(),
+            ctx.accounts.board.key(),
+            player_account.key(),
+            tournament.config.clone(),
+            Clock::get()?.unix_timestamp,
+        );
+        game.state = GameState::InProgress;
+        game.current_player = players[0];
+
+        let board = &mut ctx.accounts.board.load_init()?;
+        board.game = game.key();
+        board.copy_map_from(&*ctx.accounts.template.load()?);
+
+        seat_players(player_account, game.key(), &players);
+        deal(game, board, &player_account.players)?;
+
+        // Tables hold no stakes, but `close_game` expects every game to have a vault
+        let vault = &mut ctx.accounts.vault;
+        vault.game = game.key();
+        vault.bump = ctx.bumps.vault;
+
+        open_listing(
+            &mut ctx.accounts.listing,
+            ctx.bumps.listing,
+            game,
+            board,
+            players.len(),
+        );
+        Ok(())
+    }
+
+    // Reads a finished table's standings: in single elimination only the winner goes
+    // through, in Swiss every player scores one point per opponent finishing below them.
+    // Each player's place is kept to break ties in the final standings.
+    pub fn record_table_result(ctx: Context<RecordTableResult>, table: u8) -> Result<()> {
+        let tournament = &mut ctx.accounts.tournament;
+        let game = &ctx.accounts.game;
+        require!(game.is_finished(), ErrorCode::GameNotFinished);
+        tournament.last_activity_at = Clock::get()?.unix_timestamp;
+
+        let round = tournament.round;
+        let format = tournament.settings.format.clone();
+        let seats = {
+            let table = tournament
+                .tables
+                .get_mut(table as usize)
+                .ok_or(ErrorCode::InvalidTable)?;
+            require!(
+                table.game == game.key() && !table.recorded,
+                ErrorCode::InvalidTable
+            );
+            table.recorded = true;
+            table.seats.clone()
+        };
+
+        let players = &ctx.accounts.player_account.players;
+        let ranking = final_ranking(game, &*ctx.accounts.board.load()?, players);
+        for &seat in &seats {
+            let entry = &mut tournament.players[seat as usize];
+            let place = ranking
+                .iter()
+                .position(|p| *p == entry.pubkey)
+                .unwrap_or(ranking.len());
+            entry.last_place = place as u8;
+            match format {
+                TournamentFormat::SingleElimination => {
+                    if place != 0 {
+                        entry.eliminated_in_round = Some(round);
+                    }
+                }
+                TournamentFormat::Swiss { .. } => {
+                    entry.points += seats.len().saturating_sub(place + 1) as u16;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Pays a player's share of the prize pool by final standing; the protocol fee goes
+    // to the treasury with the first claim. A cancelled tournament refunds every entry
+    // fee instead, free of the fee.
+    pub fn claim_tournament_prize(ctx: Context<ClaimTournamentPrize>) -> Result<()> {
+        let tournament = &mut ctx.accounts.tournament;
+        require!(
+            tournament.state == TournamentState::Completed
+                || tournament.state == TournamentState::Cancelled,
+            ErrorCode::TournamentNotFinished
+        );
+
+        let protocol_fee = tournament.protocol_fee();
+        if protocol_fee > 0 && !tournament.fee_collected {
+            tournament.fee_collected = true;
+            tournament.sub_lamports(protocol_fee)?;
+            ctx.accounts.treasury.add_lamports(protocol_fee)?;
+        }
+
+        let (player, share) = tournament_payouts(tournament)
+            .into_iter()
+            .find(|&(i, _)| tournament.players[i].pubkey == ctx.accounts.payee.key())
+            .ok_or(ErrorCode::NoWinnings)?;
+        require!(
+            tournament.claimed & (1 << player) == 0,
+            ErrorCode::WinningsAlreadyClaimed
+        );
+
+        tournament.claimed |= 1 << player;
+        tournament.sub_lamports(share)?;
+        ctx.accounts.payee.add_lamports(share)?;
+        Ok(())
+    }
+
+    // Returns the tournament's and template's rent to the organizer once it is over and
+    // everything owed has been claimed. Table games are closed with `close_game`.
+    pub fn close_tournament(ctx: Context<CloseTournament>) -> Result<()> {
+        let tournament = &ctx.accounts.tournament;
+        require!(
+            tournament.state == TournamentState::Completed
+                || tournament.state == TournamentState::Cancelled,
+            ErrorCode::TournamentNotFinished
+        );
+        require!(
+            tournament_payouts(tournament)
+                .iter()
+                .all(|&(i, share)| share == 0 || tournament.claimed & (1 << i) != 0),
+            ErrorCode::WinningsUnclaimed
+        );
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(init, payer = admin, space = 8 + Config::SPACE, seeds = [CONFIG_SEED], bump)]
+    pub config: Account<'info, Config>,
+    // Only the program's upgrade authority can create the config
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::RiskGame>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ ErrorCode::NotAdmin
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ ErrorCode::NotAdmin
+    )]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ForceEndGame<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = protocol_config.admin == admin.key() @ ErrorCode::NotAdmin
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+    #[account(constraint = player_account.game == game.key())]
+    pub player_account: Account<'info, PlayerAccount>,
+    #[account(mut, constraint = listing.game == game.key())]
+    pub listing: Account<'info, GameListing>,
+    #[account(mut, seeds = [VAULT_SEED, game.key().as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, token::authority = vault)]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+    pub admin: Signer<'info>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    color: String,
+    territories: Vec<Territory>,
+    continents: Vec<Continent>,
+    config: GameConfig,
+)]
+pub struct InitializeGame<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(init, payer = creator, space = 8 + Game::space(&config))]
+    pub game: Account<'info, Game>,
+    // Too large to create here; the client allocates `8 + Board::SPACE` bytes owned by this program
+    #[account(zero)]
+    pub board: AccountLoader<'info, Board>,
+    #[account(init, payer = creator, space = 8 + PlayerAccount::space(1, &config.rules))]
+    pub player_account: Account<'info, PlayerAccount>,
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + PlayerProfile::SPACE,
+        seeds = [PROFILE_SEED, creator.key().as_ref()],
+        bump
+    )]
+    pub creator_profile: Account<'info, PlayerProfile>,
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + GameListing::SPACE,
+        seeds = [LISTING_SEED, game.key().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, GameListing>,
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Vault::SPACE,
+        seeds = [VAULT_SEED, game.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    // Token-staked games only, see `GameConfig::stake_mint`
+    pub stake_mint: Option<Account<'info, Mint>>,
+    #[account(
+        init,
+        payer = creator,
+        associated_token::mint = stake_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, token::authority = creator)]
+    pub creator_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+}
+
+#[derive(Accounts)]
+pub struct LoadMap<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(constraint = game.creator == creator.key() @ ErrorCode::NotGameCreator)]
+    pub game: Account<'info, Game>,
+    #[account(mut, constraint = board.load()?.game == game.key())]
+    pub board: AccountLoader<'info, Board>,
+    #[account(mut, constraint = listing.game == game.key())]
+    pub listing: Account<'info, GameListing>,
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AbandonGame<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+    #[account(constraint = player_account.game == game.key())]
+    pub player_account: Account<'info, PlayerAccount>,
+    #[account(mut, constraint = listing.game == game.key())]
+    pub listing: Account<'info, GameListing>,
+}
+
+#[derive(Accounts)]
+pub struct RecordGameStats<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+    #[account(constraint = board.load()?.game == game.key())]
+    pub board: AccountLoader<'info, Board>,
+    #[account(constraint = player_account.game == game.key())]
+    pub player_account: Account<'info, PlayerAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWinnings<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    pub game: Account<'info, Game>,
+    #[account(constraint = board.load()?.game == game.key())]
+    pub board: AccountLoader<'info, Board>,
+    #[account(constraint = player_account.game == game.key())]
+    pub player_account: Account<'info, PlayerAccount>,
+    #[account(mut, seeds = [VAULT_SEED, game.key().as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, token::authority = vault)]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub payee: SystemAccount<'info>,
+    #[account(mut, token::authority = payee)]
+    pub payee_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, constraint = treasury.key() == protocol_config.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: SystemAccount<'info>,
+    #[account(mut, token::authority = treasury)]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseGame<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(
+        mut,
+        close = creator,
+        constraint = game.creator == creator.key() @ ErrorCode::NotGameCreator
+    )]
+    pub game: Account<'info, Game>,
+    #[account(mut, close = creator, constraint = board.load()?.game == game.key())]
+    pub board: AccountLoader<'info, Board>,
+    #[account(mut, close = creator, constraint = player_account.game == game.key())]
+    pub player_account: Account<'info, PlayerAccount>,
+    #[account(mut, close = creator, constraint = listing.game == game.key())]
+    pub listing: Account<'info, GameListing>,
+    #[account(
+        mut,
+        close = creator,
+        seeds = [VAULT_SEED, game.key().as_ref()],
+        bump = vault.bump,
+        constraint = vault.paid_out == vault.pot @ ErrorCode::WinningsUnclaimed
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, token::authority = vault)]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+    // Optional summary written before the game's accounts go away
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + GameResult::space(&game.config.rules),
+        seeds = [GAME_RESULT_SEED, game.key().as_ref()],
+        bump
+    )]
+    pub game_result: Option<Account<'info, GameResult>>,
+    // The game's player and territory program states, closed alongside it when the
+    // creator is their authority
+    #[account(mut, constraint = player_state.game == game.key())]
+    pub player_state: Option<Account<'info, player::PlayerState>>,
+    pub player_program: Option<Program<'info, player::program::Player>>,
+    #[account(mut, constraint = territory_state.game == game.key())]
+    pub territory_state: Option<Account<'info, territory::TerritoryState>>,
+    pub territory_program: Option<Program<'info, territory::program::Territory>>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct JoinGame<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+    #[account(
+        mut,
+        constraint = player_account.game == game.key(),
+        realloc = 8 + PlayerAccount::space(player_account.players.len() + 1, &game.config.rules),
+        realloc::payer = player,
+        realloc::zero = false
+    )]
+    pub player_account: Account<'info, PlayerAccount>,
+    #[account(mut, constraint = listing.game == game.key())]
+    pub listing: Account<'info, GameListing>,
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = 8 + PlayerProfile::SPACE,
+        seeds = [PROFILE_SEED, player.key().as_ref()],
+        bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    #[account(mut, seeds = [VAULT_SEED, game.key().as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, token::authority = vault)]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub player: Signer<'info>,
+    #[account(mut, token::authority = player)]
+    pub player_token_account: Option<Account<'info, TokenAccount>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct LeaveGame<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    // The creator holds the lobby open and has to cancel it instead
+    #[account(constraint = game.creator != player.key() @ ErrorCode::CreatorCannotLeave)]
+    pub game: Account<'info, Game>,
+    #[account(
+        mut,
+        constraint = player_account.game == game.key(),
+        realloc = 8 + PlayerAccount::space(player_account.players.len() - 1, &game.config.rules),
+        realloc::payer = player,
+        realloc::zero = false
+    )]
+    pub player_account: Account<'info, PlayerAccount>,
+    #[account(mut, constraint = listing.game == game.key())]
+    pub listing: Account<'info, GameListing>,
+    #[account(mut, seeds = [VAULT_SEED, game.key().as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, token::authority = vault)]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub player: Signer<'info>,
+    #[account(mut, token::authority = player)]
+    pub player_token_account: Option<Account<'info, TokenAccount>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelGame<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(
+        mut,
+        close = creator,
+        constraint = game.creator == creator.key() @ ErrorCode::NotGameCreator
+    )]
+    pub game: Account<'info, Game>,
+    #[account(mut, close = creator, constraint = board.load()?.game == game.key())]
+    pub board: AccountLoader<'info, Board>,
+    #[account(mut, close = creator, constraint = player_account.game == game.key())]
+    pub player_account: Account<'info, PlayerAccount>,
+    #[account(mut, close = creator, constraint = listing.game == game.key())]
+    pub listing: Account<'info, GameListing>,
+    #[account(
+        mut,
+        close = creator,
+        seeds = [VAULT_SEED, game.key().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, token::authority = vault)]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    #[account(mut, token::authority = creator)]
+    pub creator_token_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct Resign<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+    #[account(mut, constraint = board.load()?.game == game.key())]
+    pub board: AccountLoader<'info, Board>,
+    #[account(mut, constraint = player_account.game == game.key())]
+    pub player_account: Account<'info, PlayerAccount>,
+    #[account(mut, constraint = listing.game == game.key())]
+    pub listing: Account<'info, GameListing>,
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CommitMission<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(
+        mut,
+        constraint = game.config.mission_dealer == Some(dealer.key()) @ ErrorCode::NotMissionDealer
+    )]
+    pub game: Account<'info, Game>,
+    #[account(mut, constraint = player_account.game == game.key())]
+    pub player_account: Account<'info, PlayerAccount>,
+    #[account(mut, constraint = listing.game == game.key())]
+    pub listing: Account<'info, GameListing>,
+    pub dealer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(target: Pubkey)]
+pub struct ProposeTruce<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    pub game: Account<'info, Game>,
+    #[account(constraint = player_account.game == game.key())]
+    pub player_account: Account<'info, PlayerAccount>,
+    #[account(
+        init_if_needed,
+        payer = proposer,
+        space = 8 + Treaty::SPACE,
+        seeds = [
+            TREATY_SEED,
+            game.key().as_ref(),
+            treaty_parties(&proposer.key(), &target).0.as_ref(),
+            treaty_parties(&proposer.key(), &target).1.as_ref(),
+        ],
+        bump
+    )]
+    pub treaty: Account<'info, Treaty>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RespondTruce<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    pub game: Account<'info, Game>,
+    #[account(mut, constraint = treaty.game == game.key())]
+    pub treaty: Account<'info, Treaty>,
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MakeMove<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+    #[account(mut, constraint = board.load()?.game == game.key())]
+    pub board: AccountLoader<'info, Board>,
+    #[account(mut, constraint = player_account.game == game.key())]
+    pub player_account: Account<'info, PlayerAccount>,
+    // Kept in step with moves that end the game
+    #[account(mut, constraint = listing.game == game.key())]
+    pub listing: Account<'info, GameListing>,
+    #[account(mut, seeds = [PROFILE_SEED, player.key().as_ref()], bump = player_profile.bump)]
+    pub player_profile: Account<'info, PlayerProfile>,
+    pub player: Signer<'info>,
+    pub player_program: Program<'info, player::program::Player>,
+    #[account(mut)]
+    pub player_state: Account<'info, player::PlayerState>,
+}
+
+#[derive(Accounts)]
+#[instruction(config: GameConfig)]
+pub struct CreateQueue<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(init, payer = creator, space = 8 + MatchQueue::space(&config))]
+    pub queue: Account<'info, MatchQueue>,
+    // Allocated by the client like `InitializeGame::board`
+    #[account(zero)]
+    pub template: AccountLoader<'info, Board>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Enqueue<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(mut)]
+    pub queue: Account<'info, MatchQueue>,
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = 8 + PlayerProfile::SPACE,
+        seeds = [PROFILE_SEED, player.key().as_ref()],
+        bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    #[account(mut)]
+    pub player: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LeaveQueue<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(mut)]
+    pub queue: Account<'info, MatchQueue>,
+    #[account(mut)]
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireQueueEntry<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(mut)]
+    pub queue: Account<'info, MatchQueue>,
+    #[account(mut)]
+    pub player: SystemAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MatchPlayers<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(mut)]
+    pub queue: Account<'info, MatchQueue>,
+    #[account(constraint = template.key() == queue.template)]
+    pub template: AccountLoader<'info, Board>,
+    #[account(init, payer = cranker, space = 8 + Game::space(&queue.config))]
+    pub game: Account<'info, Game>,
+    #[account(zero)]
+    pub board: AccountLoader<'info, Board>,
+    #[account(
+        init,
+        payer = cranker,
+        space = 8 + PlayerAccount::space(queue.player_count as usize, &queue.config.rules)
+    )]
+    pub player_account: Account<'info, PlayerAccount>,
+    #[account(
+        init,
+        payer = cranker,
+        space = 8 + Vault::SPACE,
+        seeds = [VAULT_SEED, game.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = cranker,
+        space = 8 + GameListing::SPACE,
+        seeds = [LISTING_SEED, game.key().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, GameListing>,
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseQueue<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(
+        mut,
+        close = creator,
+        constraint = queue.creator == creator.key() @ ErrorCode::NotGameCreator
+    )]
+    pub queue: Account<'info, MatchQueue>,
+    #[account(mut, close = creator, constraint = template.key() == queue.template)]
+    pub template: AccountLoader<'info, Board>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(config: GameConfig, settings: TournamentSettings)]
+pub struct CreateTournament<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(init, payer = organizer, space = 8 + Tournament::space(&config, &settings))]
+    pub tournament: Account<'info, Tournament>,
+    // Allocated by the client like `InitializeGame::board`
+    #[account(zero)]
+    pub template: AccountLoader<'info, Board>,
+    #[account(mut)]
+    pub organizer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterForTournament<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(mut)]
+    pub tournament: Account<'info, Tournament>,
+    #[account(mut)]
+    pub player: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LeaveTournament<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(mut)]
+    pub tournament: Account<'info, Tournament>,
+    #[account(mut)]
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTournament<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(mut)]
+    pub tournament: Account<'info, Tournament>,
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StartRound<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(
+        mut,
+        constraint = tournament.organizer == organizer.key() @ ErrorCode::NotGameCreator
+    )]
+    pub tournament: Account<'info, Tournament>,
+    pub organizer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SpawnTable<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(
+        mut,
+        constraint = tournament.organizer == organizer.key() @ ErrorCode::NotGameCreator
+    )]
+    pub tournament: Account<'info, Tournament>,
+    #[account(constraint = template.key() == tournament.template)]
+    pub template: AccountLoader<'info, Board>,
+    #[account(init, payer = organizer, space = 8 + Game::space(&tournament.config))]
+    pub game: Account<'info, Game>,
+    #[account(zero)]
+    pub board: AccountLoader<'info, Board>,
+    #[account(
+        init,
+        payer = organizer,
+        space = 8 + PlayerAccount::space(
+            tournament.settings.table_size as usize,
+            &tournament.config.rules
+        )
+    )]
+    pub player_account: Account<'info, PlayerAccount>,
+    #[account(
+        init,
+        payer = organizer,
+        space = 8 + Vault::SPACE,
+        seeds = [VAULT_SEED, game.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = organizer,
+        space = 8 + GameListing::SPACE,
+        seeds = [LISTING_SEED, game.key().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, GameListing>,
+    #[account(mut)]
+    pub organizer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecordTableResult<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(mut)]
+    pub tournament: Account<'info, Tournament>,
+    pub game: Account<'info, Game>,
+    #[account(constraint = board.load()?.game == game.key())]
+    pub board: AccountLoader<'info, Board>,
+    #[account(constraint = player_account.game == game.key())]
+    pub player_account: Account<'info, PlayerAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimTournamentPrize<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(mut)]
+    pub tournament: Account<'info, Tournament>,
+    #[account(mut)]
+    pub payee: SystemAccount<'info>,
+    #[account(mut, constraint = treasury.key() == protocol_config.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: SystemAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTournament<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = !protocol_config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub protocol_config: Account<'info, Config>,
+    #[account(
+        mut,
+        close = organizer,
+        constraint = tournament.organizer == organizer.key() @ ErrorCode::NotGameCreator
+    )]
+    pub tournament: Account<'info, Tournament>,
+    #[account(mut, close = organizer, constraint = template.key() == tournament.template)]
+    pub template: AccountLoader<'info, Board>,
+    #[account(mut)]
+    pub organizer: Signer<'info>,
+}
+
+#[account]
+pub struct Game {
+    pub creator: Pubkey,
+    pub current_player: Pubkey,
+    pub turn: u32,
+    pub state: GameState,
+    pub current_phase: TurnPhase,
+    pub cards_sets_turned_in: u8,
+    pub last_attack_dice: Option<AttackDice>,
+    pub pending_reinforcements: Option<u16>,
+    pub board: Pubkey,
+    pub player_account: Pubkey,
+    pub config: GameConfig,
+    pub winner: Option<Pubkey>,
+    // Final standings, filled in when the turn limit ends the game
+    pub ranking: Vec<Pubkey>,
+    pub winning_team: Option<u8>,
+    pub fortifications_this_turn: u8,
+    // Unix timestamp of the last turn change, used to detect abandoned games
+    pub turn_started_at: i64,
+    // Set once the result has been credited to player profiles
+    pub stats_recorded: bool,
+    // Filled through `join_game`, where every seat after the creator's paid for its slot
+    pub lobby: bool,
+    // Set once territories have been dealt, which happens only once per game
+    pub dealt: bool,
+}
+
+// One per pair of players, see `treaty_parties`
+#[account]
+pub struct Treaty {
+    pub game: Pubkey,
+    pub proposer: Pubkey,
+    pub target: Pubkey,
+    pub turns: u32,
+    pub expires_at_turn: u32,
+    pub status: TreatyStatus,
+    pub broken_by: Option<Pubkey>,
+    pub bump: u8,
+}
+
+// Program-wide settings, one per deployment
+#[account]
+pub struct Config {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    // Cut of every prize pool, in basis points
+    pub protocol_fee_bps: u16,
+    pub bump: u8,
+    // When `set_paused` last lifted a pause; turns don't age while paused
+    pub resumed_at: i64,
+    // Read by the territory and player programs at a fixed offset, keep it last
+    pub paused: bool,
+}
+
+// Career stats for one wallet across every game it has played
+#[account]
+pub struct PlayerProfile {
+    pub player: Pubkey,
+    pub games_played: u32,
+    pub wins: u32,
+    // Opponents knocked out by taking their last territory
+    pub eliminations: u32,
+    pub territories_conquered: u32,
+    // Attack dice only; defense rolls happen on the attacker's turn
+    pub dice_rolled: u64,
+    // Finished games per `PlayerColor`
+    pub games_per_color: [u32; 6],
+    pub favorite_color: Option<u8>,
+    // The pools this wallet has played in most, see `GameConfig::rating_pool`
+    pub ratings: [PoolRating; RATING_POOLS],
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct PoolRating {
+    // `GameConfig::rating_pool` this rating is kept in
+    pub pool: [u8; 32],
+    // Fixed point with `RATING_SCALE` units per Elo point; unset until the first rated game
+    pub rating: i32,
+    pub games: u32,
+}
+
+// Players waiting to be seated together, see `match_players`. The player count and
+// config are fixed per queue; players wanting other settings join another queue.
+#[account]
+pub struct MatchQueue {
+    pub creator: Pubkey,
+    // Every matched game is created with this config
+    pub config: GameConfig,
+    // Seats per matched game
+    pub player_count: u8,
+    // Board holding the map matched games copy; its `game` is this queue
+    pub template: Pubkey,
+    // `GameConfig::rating_pool` of the config on the template's map
+    pub rating_pool: [u8; 32],
+    pub entries: Vec<QueueEntry>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct QueueEntry {
+    pub player: Pubkey,
+    // The player's rating in the queue's pool when they joined
+    pub rating: i64,
+    // Widest rating gap, in Elo points, the player accepts to any opponent
+    pub rating_band: u32,
+    pub enqueued_at: i64,
+}
+
+// A bracket of tables played on one map, see `start_round`. Entry fees are held in
+// this account until `claim_tournament_prize`.
+#[account]
+pub struct Tournament {
+    pub organizer: Pubkey,
+    // Every table is created with this config
+    pub config: GameConfig,
+    pub settings: TournamentSettings,
+    // Board holding the map every table copies; its `game` is this tournament
+    pub template: Pubkey,
+    pub state: TournamentState,
+    // Rounds started so far
+    pub round: u8,
+    // In registration order, which is also the seeding
+    pub players: Vec<TournamentPlayer>,
+    // Tables of the current round
+    pub tables: Vec<TournamentTable>,
+    pub pot: u64,
+    // Taken from `Config` when the tournament is created
+    pub protocol_fee_bps: u16,
+    pub fee_collected: bool,
+    // One bit per player whose prize has been paid
+    pub claimed: u64,
+    // Unix timestamp of the last registration, round or table result, see `cancel_tournament`
+    pub last_activity_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TournamentSettings {
+    pub format: TournamentFormat,
+    // Most players seated at one table
+    pub table_size: u8,
+    pub max_players: u8,
+    pub entry_fee: Option<u64>,
+    // Percentages of the pot by final standing; must add up to 100
+    pub prize_split: Vec<u8>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub enum TournamentFormat {
+    // Only each table's winner plays on
+    SingleElimination,
+    // Everyone plays every round, seated by score
+    Swiss { rounds: u8 },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub enum TournamentState {
+    Registration,
+    InProgress,
+    Completed,
+    // Entry fees are refunded, see `cancel_tournament`
+    Cancelled,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TournamentPlayer {
+    pub pubkey: Pubkey,
+    // Swiss score, see `record_table_result`
+    pub points: u16,
+    pub eliminated_in_round: Option<u8>,
+    // Place at the player's most recent table, 0 for its winner
+    pub last_place: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TournamentTable {
+    // `Pubkey::default()` until `spawn_table`
+    pub game: Pubkey,
+    // Indices into `Tournament::players`, in seat order
+    pub seats: Vec<u8>,
+    pub recorded: bool,
+}
+
+// Lobby browser entry for one game, at `[LISTING_SEED, game]`. Fields up to `bump` have
+// fixed offsets so clients can filter listings with memcmp, see `GameListing::STATE_OFFSET`.
+#[account]
+pub struct GameListing {
+    pub state: GameState,
+    pub player_count: u8,
+    pub max_players: u8,
+    pub game: Pubkey,
+    pub creator: Pubkey,
+    // `Board::map_hash`, the same for every game on a given map
+    pub map_hash: [u8; 32],
+    pub created_at: i64,
+    pub bump: u8,
+    pub entry_fee: Option<u64>,
+    pub stake_mint: Option<Pubkey>,
+}
+
+// Escrows entry fees until the game is settled. Every game has one; free games leave it empty.
+#[account]
+pub struct Vault {
+    pub game: Pubkey,
+    pub pot: u64,
+    pub paid_out: u64,
+    // Taken from `Config` when the game is created
+    pub protocol_fee_bps: u16,
+    pub fee_collected: bool,
+    // One bit per seat in `PlayerAccount::players`
+    pub claimed_seats: u8,
+    pub bump: u8,
+}
+
+// Outlives the game so results stay readable after its accounts are closed
+#[account]
+pub struct GameResult {
     pub game: Pubkey,
+    pub creator: Pubkey,
+    pub winner: Option<Pubkey>,
+    pub winning_team: Option<u8>,
+    pub ranking: Vec<Pubkey>,
+    pub turns: u32,
+    pub abandoned: bool,
+    pub closed_at: i64,
+    pub bump: u8,
+}
+
+pub const MAX_TERRITORIES: usize = 256;
+pub const MAX_CONTINENTS: usize = 64;
+pub const MAX_ADJACENCY: usize = 4096;
+
+// Fixed-size records read in place, so a move only touches the territories it names
+#[account(zero_copy)]
+pub struct Board {
+    pub game: Pubkey,
+    pub territory_count: u16,
+    pub adjacency_len: u16,
+    pub continent_count: u8,
+    pub _padding: [u8; 1],
+    pub territories: [TerritoryRecord; MAX_TERRITORIES],
+    // Indexed by continent id
+    pub continent_bonuses: [u8; MAX_CONTINENTS],
+    // Every territory's neighbours packed back to back, see `TerritoryRecord::adjacency_start`
+    pub adjacency: [u8; MAX_ADJACENCY],
+}
+
+#[zero_copy]
+pub struct TerritoryRecord {
+    // `Pubkey::default()` when unowned or held by the neutral army
+    pub owner: Pubkey,
+    pub troops: u16,
+    pub continent_id: u8,
+    pub adjacency_len: u8,
+    pub adjacency_start: u16,
+}
+
+#[account]
+pub struct PlayerAccount {
+    pub players: Vec<PlayerState>,
+    pub game: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PlayerState {
+    pub pubkey: Pubkey,
+    pub color_id: u8,
+    pub cards: Vec<RiskCard>,
+    pub conquered_territory_this_turn: bool,
+    // Hash of the secret mission dealt to this player, see `mission_commitment`
+    pub mission_commitment: Option<[u8; 32]>,
+    pub capital: Option<u8>,
+    // Whoever owns the capital territory now; `None` once it falls to the neutral army
+    pub capital_holder: Option<Pubkey>,
+    pub team_id: Option<u8>,
+    pub eliminated: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Territory {
+    pub id: u8,
+    pub continent_id: u8,
+    pub owner: Option<Pubkey>,
+    pub troops: u16,
+    pub adjacent_territories: Vec<u8>,
+}
+
+// Map definition passed to `initialize_game` and `load_map`. The board takes continent
+// membership from `Territory::continent_id` and only stores each continent's bonus.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Continent {
+    pub id: u8,
+    pub territories: Vec<u8>,
+    pub bonus_armies: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RiskCard {
+    pub card_type: CardType,
+    pub territory_id: Option<u8>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AttackDice {
+    pub attacker: Vec<u8>,
+    pub defender: Vec<u8>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub enum GameState {
+    Setup,
+    InProgress,
+    Completed,
+    // Ended by the admin with every stake refunded, see `force_end_game`
+    Aborted,
+    // Left idle past `Game::ABANDONED_AFTER`, see `abandon_game`
+    Abandoned,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub enum TreatyStatus {
+    Proposed,
+    Active,
+    Broken,
+}
+
+#[event]
+pub struct TruceBroken {
+    pub game: Pubkey,
+    pub treaty: Pubkey,
+    pub broken_by: Pubkey,
+    pub turn: u32,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub enum TurnPhase {
+    Reinforcement,
+    Attack,
+    Fortification,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Hash)]
+pub enum CardType {
+    Infantry,
+    Cavalry,
+    Artillery,
+    Wild,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct GameConfig {
+    pub card_bonus_schedule: CardBonusSchedule,
+    pub rules: RuleSet,
+    pub victory_mode: VictoryMode,
+    // Counted the same way as `Game::turn`; when passed the game ends and players are ranked
+    pub max_turns: Option<u32>,
+    // Stake each player puts up on joining, see `Vault`: lamports, or `stake_mint` tokens
+    pub entry_fee: Option<u64>,
+    pub stake_mint: Option<Pubkey>,
+    pub prize_split: PrizeSplit,
+    // Deals the secret missions; required in that mode and may not take a seat
+    pub mission_dealer: Option<Pubkey>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub enum VictoryMode {
+    WorldDomination,
+    // The mission dealer hands each player a hidden mission before the game starts, revealed
+    // with `claim_mission_victory`. World domination still wins outright.
+    SecretMission,
+    // Each player picks a capital after distribution; holding every capital wins
+    Capital,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub enum Mission {
+    ConquerContinents(Vec<u8>),
+    // Falls back to holding `fallback_territories` if the color is the claimant's or not in play
+    EliminateColor {
+        color_id: u8,
+        fallback_territories: u8,
+    },
+    HoldTerritories {
+        count: u8,
+        min_troops: u8,
+    },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub struct RuleSet {
+    pub min_reinforcements: u8,
+    pub initial_troops: u8,
+    pub max_attack_dice: u8,
+    pub max_defense_dice: u8,
+    pub card_limit: u8,
+    pub min_players: u8,
+    pub max_players: u8,
+    // Territories with no owner but troops belong to a neutral army that only defends
+    pub neutral_army: bool,
+    // Every player must pick a team; all territories held by one team wins
+    pub team_play: bool,
+    pub team_attacks: bool,
+    // None allows any number of fortify moves per turn
+    pub max_fortifications: Option<u8>,
+    // Fortify only between adjacent territories instead of along any connected path
+    pub adjacent_fortify_only: bool,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self {
+            min_reinforcements: 3,
+            initial_troops: 3,
+            max_attack_dice: 3,
+            max_defense_dice: 2,
+            card_limit: 5,
+            min_players: 2,
+            max_players: 6,
+            neutral_army: true,
+            team_play: false,
+            team_attacks: false,
+            max_fortifications: Some(1),
+            adjacent_fortify_only: false,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub enum CardBonusSchedule {
+    // 4, 6, 8, 10, 12, 15, then +5 for every further set
+    Progressive,
+    // 4 infantry, 6 cavalry, 8 artillery, 10 for one of each (or any set with a wild)
+    FixedValue,
+    // Bonus for the nth set turned in; the last entry repeats once the table runs out
+    Custom(Vec<u8>),
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub enum PrizeSplit {
+    WinnerTakesAll,
+    // Percentages of the pot by final standing, e.g. [70, 30]; must add up to 100
+    Ranked(Vec<u8>),
+    // In proportion to the territories each player holds at the end
+    Territories,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub struct TerritoryPlacement {
+    pub territory_id: u8,
+    pub troops: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub struct FortifyMove {
+    pub from_territory: u8,
+    pub to_territory: u8,
+    pub troops: u16,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    GameAlreadyStarted,
+    GameNotInProgress,
+    NotPlayerTurn,
+    MaxPlayersReached,
+    NotTerritoryOwner,
+    InsufficientTroops,
+    CannotAttackOwnTerritory,
+    InvalidPhase,
+    InvalidCardSet,
+    InvalidDiceCount,
+    TerritoriesNotAdjacent,
+    NoRemainingMoves,
+    InvalidTroopCount,
+    CardLimitReached,
+    ColorAlreadyTaken,
+    NoReinforcements,
+    InvalidCardIndex,
+    TerritoriesNotConnected,
+    InvalidColor,
+    InvalidGameConfig,
+    TroopOverflow,
+    NotGameCreator,
+    PlayerNotFound,
+    InvalidVictoryMode,
+    MissionCommitmentClosed,
+    MissionAlreadyCommitted,
+    MissionNotCommitted,
+    MissionMismatch,
+    MissionNotComplete,
+    InvalidTerritory,
+    CapitalSelectionClosed,
+    CapitalAlreadySelected,
+    CapitalsNotSelected,
+    InvalidTeam,
+    CannotAttackTeammate,
+    InvalidTruce,
+    NotTreatyParty,
+    TruceActive,
+    TreatyAccountMissing,
+    FortificationLimitReached,
+    InvalidMap,
+    MapTooLarge,
+    GameNotFinished,
+    CreatorCannotLeave,
+    PlayerEliminated,
+    InvalidHeir,
+    PotOverflow,
+    NoWinnings,
+    WinningsAlreadyClaimed,
+    WinningsUnclaimed,
+    StakeAccountMissing,
+    NotAdmin,
+    InvalidProtocolFee,
+    InvalidTreasury,
+    ProgramPaused,
+    GameAlreadyEnded,
+    StatsAlreadyRecorded,
+    QueueFull,
+    AlreadyQueued,
+    NotQueued,
+    QueueEntryNotExpired,
+    QueueNotEmpty,
+    InvalidMatch,
+    TournamentAlreadyStarted,
+    TournamentNotInProgress,
+    TournamentNotFinished,
+    TournamentFinished,
+    NotEnoughPlayers,
+    RoundNotFinished,
+    InvalidTable,
+    DealerCannotPlay,
+    NotMissionDealer,
+    InvalidMission,
+    GameNotAbandoned,
+    TournamentNotStalled,
+    AlreadyDealt,
+}
+
+impl Game {
+    // A game nobody has moved in for a week can be ended with `abandon_game`
+    pub const ABANDONED_AFTER: i64 = 7 * 24 * 60 * 60;
+
+    // Seconds the current turn has run at `now`, not counting any pause lifted at `resumed_at`
+    pub fn idle_for(&self, resumed_at: i64, now: i64) -> i64 {
+        now.saturating_sub(self.turn_started_at.max(resumed_at))
+    }
+
+    // Whether stakes can be settled and the accounts closed
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.state,
+            GameState::Completed | GameState::Aborted | GameState::Abandoned
+        )
+    }
+
+    pub fn space(config: &GameConfig) -> usize {
+        32 + // creator
+        32 + // current_player
+        4 + // turn
+        1 + // state enum
+        1 + // current_phase enum
+        1 + // cards_sets_turned_in
+        1 + AttackDice::space(&config.rules) + // last_attack_dice Option
+        1 + 2 + // pending_reinforcements Option
+        32 + // board pubkey
+        32 + // player_account pubkey
+        config.space() +
+        1 + 32 + // winner Option
+        4 + (32 * config.rules.max_players as usize) + // ranking vec
+        1 + 1 + // winning_team Option
+        1 + // fortifications_this_turn
+        8 + // turn_started_at
+        1 + // stats_recorded
+        1 + // lobby
+        1 // dealt
+    }
+}
+
+impl GameConfig {
+    pub const MAX_CUSTOM_BONUS_STEPS: usize = 16;
+
+    pub fn space(&self) -> usize {
+        self.card_bonus_schedule.space() +
+        RuleSet::SPACE +
+        1 + // victory_mode enum
+        1 + 4 + // max_turns Option
+        1 + 8 + // entry_fee Option
+        1 + 32 + // stake_mint Option
+        self.prize_split.space() +
+        1 + 32 // mission_dealer Option
+    }
+
+    // Key of the pool games with this config on the map with `map_hash` are rated in.
+    // Everything that changes how the game plays counts; stakes and payouts do not.
+    pub fn rating_pool(&self, map_hash: &[u8; 32]) -> [u8; 32] {
+        let ruleset = (
+            &self.card_bonus_schedule,
+            &self.rules,
+            &self.victory_mode,
+            self.max_turns,
+        )
+            .try_to_vec()
+            .unwrap();
+        anchor_lang::solana_program::hash::hashv(&[&ruleset, map_hash]).to_bytes()
+    }
+}
+
+impl CardBonusSchedule {
+    pub fn space(&self) -> usize {
+        match self {
+            CardBonusSchedule::Custom(table) => 1 + 4 + table.len(),
+            _ => 1,
+        }
+    }
+}
+
+impl PrizeSplit {
+    pub fn space(&self) -> usize {
+        match self {
+            PrizeSplit::Ranked(percentages) => 1 + 4 + percentages.len(),
+            _ => 1,
+        }
+    }
+}
+
+impl RuleSet {
+    pub const MAX_DICE: u8 = 6;
+    pub const MAX_CARD_LIMIT: u8 = 10;
+    pub const MAX_PLAYERS: u8 = 6; // one per PlayerColor
+
+    pub const SPACE: usize = 7 + // u8 limits
+        1 + // neutral_army
+        1 + // team_play
+        1 + // team_attacks
+        1 + 1 + // max_fortifications Option
+        1; // adjacent_fortify_only
+}
+
+impl Mission {
+    // Refuses missions that ask for nothing or that the initial deal could already satisfy,
+    // i.e. holding no more than an even share of the map
+    pub fn validate(&self, board: &Board, player_count: usize) -> Result<()> {
+        let even_share = board.territories().len() / player_count.max(1);
+        let valid = match self {
+            Mission::ConquerContinents(continent_ids) => {
+                !continent_ids.is_empty()
+                    && continent_ids
+                        .iter()
+                        .enumerate()
+                        .all(|(i, id)| !continent_ids[..i].contains(id))
+                    && continent_ids.iter().all(|&continent_id| {
+                        board
+                            .territories()
+                            .iter()
+                            .any(|t| t.continent_id == continent_id)
+                    })
+            }
+            Mission::EliminateColor {
+                color_id,
+                fallback_territories,
+            } => *color_id < RuleSet::MAX_PLAYERS && *fallback_territories as usize > even_share,
+            Mission::HoldTerritories { count, .. } => *count as usize > even_share,
+        };
+        require!(valid, ErrorCode::InvalidMission);
+        Ok(())
+    }
+}
+
+pub const TREATY_SEED: &[u8] = b"treaty";
+pub const GAME_RESULT_SEED: &[u8] = b"game_result";
+pub const VAULT_SEED: &[u8] = b"vault";
+pub const CONFIG_SEED: &[u8] = b"config";
+pub const PROFILE_SEED: &[u8] = b"profile";
+pub const LISTING_SEED: &[u8] = b"listing";
+pub const RATING_POOLS: usize = 8;
+pub const RATING_SCALE: i64 = 100;
+pub const INITIAL_RATING: i64 = 1500 * RATING_SCALE;
+// Full K-factor, shared across all of a player's pairwise results in a game
+pub const ELO_K: i64 = 32;
+
+// Expected score of the higher rated player in parts per million, every 25 Elo points
+// of rating difference up to 800
+const ELO_EXPECTED_PPM: [i64; 33] = [
+    500000, 535916, 571463, 606288, 640065, 672510, 703385, 732507, 759747, 785027, 808318, 829633,
+    849020, 866557, 882338, 896477, 909091, 920305, 930242, 939022, 946760, 953565, 959537, 964769,
+    969347, 973346, 976836, 979878, 982528, 984834, 986840, 988584, 990099,
+];
+
+impl Config {
+    pub const MAX_PROTOCOL_FEE_BPS: u16 = 1_000;
+
+    pub const SPACE: usize = 32 + // admin pubkey
+        32 + // treasury pubkey
+        2 + // protocol_fee_bps
+        1 + // bump
+        8 + // resumed_at
+        1; // paused
+}
+
+impl PlayerProfile {
+    pub const SPACE: usize = 32 + // player pubkey
+        4 + // games_played
+        4 + // wins
+        4 + // eliminations
+        4 + // territories_conquered
+        8 + // dice_rolled
+        (4 * 6) + // games_per_color
+        1 + 1 + // favorite_color Option
+        (PoolRating::SPACE * RATING_POOLS) + // ratings
+        1; // bump
+
+    pub fn record_game(&mut self, color_id: u8, won: bool) {
+        self.games_played = self.games_played.saturating_add(1);
+        if won {
+            self.wins = self.wins.saturating_add(1);
+        }
+
+        let color = color_id as usize;
+        self.games_per_color[color] = self.games_per_color[color].saturating_add(1);
+        let favorite = self
+            .favorite_color
+            .map_or(0, |c| self.games_per_color[c as usize]);
+        if self.games_per_color[color] > favorite {
+            self.favorite_color = Some(color_id);
+        }
+    }
+
+    pub fn rating(&self, pool: &[u8; 32]) -> i64 {
+        self.ratings
+            .iter()
+            .find(|r| r.games > 0 && r.pool == *pool)
+            .map_or(INITIAL_RATING, PoolRating::current)
+    }
+
+    // Once every slot is taken, a new pool replaces the one played least
+    pub fn rate(&mut self, pool: [u8; 32], change: i64) {
+        let slot = match self
+            .ratings
+            .iter()
+            .position(|r| r.games > 0 && r.pool == pool)
+        {
+            Some(slot) => slot,
+            None => {
+                let slot = (0..RATING_POOLS)
+                    .min_by_key(|&i| self.ratings[i].games)
+                    .unwrap();
+                self.ratings[slot] = PoolRating {
+                    pool,
+                    ..PoolRating::default()
+                };
+                slot
+            }
+        };
+        self.ratings[slot].apply(change);
+    }
+}
+
+impl MatchQueue {
+    pub const MAX_ENTRIES: usize = 32;
+    // Entries older than this can be refunded by anyone with `expire_queue_entry`
+    pub const ENTRY_TIMEOUT: i64 = 60 * 60;
+
+    pub fn space(config: &GameConfig) -> usize {
+        32 + // creator
+        config.space() +
+        1 + // player_count
+        32 + // template pubkey
+        32 + // rating_pool
+        4 + (QueueEntry::SPACE * MatchQueue::MAX_ENTRIES) // entries vec
+    }
+}
+
+impl QueueEntry {
+    pub const SPACE: usize = 32 + // player pubkey
+        8 + // rating
+        4 + // rating_band
+        8; // enqueued_at
+}
+
+impl Tournament {
+    // Bounded by the `claimed` bitmap
+    pub const MAX_PLAYERS: u8 = 64;
+    // Seats per table, within what a classic board plays well with
+    pub const TABLE_SIZES: std::ops::RangeInclusive<u8> = 4..=6;
+    // Without a registration, round or result for this long, anyone may cancel
+    pub const STALLED_AFTER: i64 = 7 * 24 * 60 * 60;
+
+    pub fn space(config: &GameConfig, settings: &TournamentSettings) -> usize {
+        let max_players = settings.max_players as usize;
+        let max_tables = max_players.div_ceil(2);
+        32 + // organizer
+        config.space() +
+        settings.space() +
+        32 + // template pubkey
+        1 + // state enum
+        1 + // round
+        4 + (TournamentPlayer::SPACE * max_players) + // players vec
+        4 + (TournamentTable::space(settings.table_size) * max_tables) + // tables vec
+        8 + // pot
+        2 + // protocol_fee_bps
+        1 + // fee_collected
+        8 + // claimed
+        8 // last_activity_at
+    }
+
+    // Charged on prizes only, never on refunds
+    pub fn protocol_fee(&self) -> u64 {
+        if self.state == TournamentState::Cancelled {
+            return 0;
+        }
+        (u128::from(self.pot) * u128::from(self.protocol_fee_bps) / 10_000) as u64
+    }
+}
+
+impl TournamentSettings {
+    pub fn space(&self) -> usize {
+        1 + 1 + // format enum with Swiss rounds
+        1 + // table_size
+        1 + // max_players
+        1 + 8 + // entry_fee Option
+        4 + self.prize_split.len() // prize_split vec
+    }
+}
+
+impl TournamentPlayer {
+    pub const SPACE: usize = 32 + // pubkey
+        2 + // points
+        1 + 1 + // eliminated_in_round Option
+        1; // last_place
+}
+
+impl TournamentTable {
+    pub fn space(table_size: u8) -> usize {
+        32 + // game pubkey
+        4 + table_size as usize + // seats vec
+        1 // recorded
+    }
+}
+
+impl GameListing {
+    // Byte offset of `state` in the account data, discriminator included
+    pub const STATE_OFFSET: usize = 8;
+
+    pub const SPACE: usize = 1 + // state enum
+        1 + // player_count
+        1 + // max_players
+        32 + // game pubkey
+        32 + // creator pubkey
+        32 + // map_hash
+        8 + // created_at
+        1 + // bump
+        1 + 8 + // entry_fee Option
+        1 + 32; // stake_mint Option
+
+    pub fn sync(&mut self, game: &Game, player_count: usize) {
+        self.state = game.state.clone();
+        self.player_count = player_count as u8;
+    }
+}
+
+impl PoolRating {
+    pub const SPACE: usize = 32 + // pool
+        4 + // rating
+        4; // games
+
+    pub fn current(&self) -> i64 {
+        if self.games == 0 {
+            INITIAL_RATING
+        } else {
+            i64::from(self.rating)
+        }
+    }
+
+    pub fn apply(&mut self, change: i64) {
+        self.rating = (self.current() + change).clamp(0, i64::from(i32::MAX)) as i32;
+        self.games = self.games.saturating_add(1);
+    }
+}
+
+impl Vault {
+    pub const SPACE: usize = 32 + // game pubkey
+        8 + // pot
+        8 + // paid_out
+        2 + // protocol_fee_bps
+        1 + // fee_collected
+        1 + // claimed_seats
+        1; // bump
+
+    // Only prizes are charged; stakes handed back from an abandoned game are not
+    pub fn protocol_fee(&self, game: &Game) -> u64 {
+        if game.state != GameState::Completed {
+            return 0;
+        }
+        (u128::from(self.pot) * u128::from(self.protocol_fee_bps) / 10_000) as u64
+    }
+
+    // Takes a stake back out of the pot, never more than what is left unpaid
+    pub fn take_refund(&mut self, entry_fee: u64) -> Result<u64> {
+        let unpaid = self
+            .pot
+            .checked_sub(self.paid_out)
+            .ok_or(ErrorCode::PotOverflow)?;
+        let refund = entry_fee.min(unpaid);
+        self.pot -= refund;
+        Ok(refund)
+    }
+}
+
+impl Treaty {
+    pub const SPACE: usize = 32 + // game pubkey
+        32 + // proposer pubkey
+        32 + // target pubkey
+        4 + // turns
+        4 + // expires_at_turn
+        1 + // status enum
+        1 + 32 + // broken_by Option
+        1; // bump
+
+    pub fn is_active(&self, turn: u32) -> bool {
+        self.status == TreatyStatus::Active && turn < self.expires_at_turn
+    }
+}
+
+impl GameResult {
+    pub fn space(rules: &RuleSet) -> usize {
+        32 + // game pubkey
+        32 + // creator pubkey
+        1 + 32 + // winner Option
+        1 + 1 + // winning_team Option
+        4 + (32 * rules.max_players as usize) + // ranking vec
+        4 + // turns
+        1 + // abandoned
+        8 + // closed_at
+        1 // bump
+    }
+}
+
+impl AttackDice {
+    pub fn space(rules: &RuleSet) -> usize {
+        4 + rules.max_attack_dice as usize + // attacker vec
+        4 + rules.max_defense_dice as usize // defender vec
+    }
+}
+
+impl Board {
+    pub const SPACE: usize = std::mem::size_of::<Board>();
+
+    pub fn territories(&self) -> &[TerritoryRecord] {
+        &self.territories[..self.territory_count as usize]
+    }
+
+    pub fn territory(&self, id: u8) -> Result<&TerritoryRecord> {
+        self.territories()
+            .get(id as usize)
+            .ok_or_else(|| error!(ErrorCode::InvalidTerritory))
+    }
+
+    pub fn territory_mut(&mut self, id: u8) -> Result<&mut TerritoryRecord> {
+        let territory_count = self.territory_count as usize;
+        self.territories[..territory_count]
+            .get_mut(id as usize)
+            .ok_or_else(|| error!(ErrorCode::InvalidTerritory))
+    }
+
+    pub fn adjacent_territories(&self, territory: &TerritoryRecord) -> &[u8] {
+        let start = territory.adjacency_start as usize;
+        &self.adjacency[start..start + territory.adjacency_len as usize]
+    }
+
+    pub fn owns_continent(&self, continent_id: u8, owner: &Pubkey) -> bool {
+        let mut members = self
+            .territories()
+            .iter()
+            .filter(|t| t.continent_id == continent_id)
+            .peekable();
+        members.peek().is_some() && members.all(|t| t.owner() == Some(*owner))
+    }
+
+    // Sum of the bonuses for every continent `owner` holds outright, in one pass over the board
+    pub fn continent_bonus(&self, owner: &Pubkey) -> u16 {
+        let mut held = [false; MAX_CONTINENTS];
+        let mut contested = [false; MAX_CONTINENTS];
+        for territory in self.territories() {
+            let continent = territory.continent_id as usize;
+            if territory.owner() == Some(*owner) {
+                held[continent] = true;
+            } else {
+                contested[continent] = true;
+            }
+        }
+
+        (0..self.continent_count as usize)
+            .filter(|&c| held[c] && !contested[c])
+            .map(|c| u16::from(self.continent_bonuses[c]))
+            .sum()
+    }
+
+    // Maps too big for one transaction arrive in chunks; each chunk's ids continue
+    // where the previous one stopped.
+    pub fn append_map(
+        &mut self,
+        territories: &[Territory],
+        continents: &[Continent],
+    ) -> Result<()> {
+        for territory in territories {
+            let id = self.territory_count as usize;
+            let start = self.adjacency_len as usize;
+            let adjacency_len = territory.adjacent_territories.len();
+            require!(
+                id < MAX_TERRITORIES
+                    && adjacency_len <= u8::MAX as usize
+                    && start + adjacency_len <= MAX_ADJACENCY,
+                ErrorCode::MapTooLarge
+            );
+            require!(
+                territory.id as usize == id && (territory.continent_id as usize) < MAX_CONTINENTS,
+                ErrorCode::InvalidMap
+            );
+
+            self.adjacency[start..start + adjacency_len]
+                .copy_from_slice(&territory.adjacent_territories);
+            let record = &mut self.territories[id];
+            record.set_owner(territory.owner);
+            record.troops = territory.troops;
+            record.continent_id = territory.continent_id;
+            record.adjacency_len = adjacency_len as u8;
+            record.adjacency_start = start as u16;
+
+            self.territory_count += 1;
+            self.adjacency_len += adjacency_len as u16;
+        }
+
+        for continent in continents {
+            require!(
+                (continent.id as usize) < MAX_CONTINENTS,
+                ErrorCode::InvalidMap
+            );
+            self.continent_bonuses[continent.id as usize] = continent.bonus_armies;
+            self.continent_count = self.continent_count.max(continent.id + 1);
+        }
+        Ok(())
+    }
+
+    // Copied field by field to keep the board off the stack
+    pub fn copy_map_from(&mut self, template: &Board) {
+        self.territory_count = template.territory_count;
+        self.adjacency_len = template.adjacency_len;
+        self.continent_count = template.continent_count;
+        self.territories.copy_from_slice(&template.territories);
+        self.continent_bonuses
+            .copy_from_slice(&template.continent_bonuses);
+        self.adjacency.copy_from_slice(&template.adjacency);
+    }
+
+    // Fingerprint of the map alone: continents, adjacency and bonuses, but not who holds what
+    pub fn map_hash(&self) -> [u8; 32] {
+        let layout: Vec<u8> = self
+            .territories()
+            .iter()
+            .flat_map(|t| [t.continent_id, t.adjacency_len])
+            .collect();
+        anchor_lang::solana_program::hash::hashv(&[
+            &layout,
+            &self.adjacency[..self.adjacency_len as usize],
+            &self.continent_bonuses[..self.continent_count as usize],
+        ])
+        .to_bytes()
+    }
+
+    // Adjacency may point into a later chunk, so it can only be checked once the map is complete
+    pub fn validate_map(&self) -> Result<()> {
+        require!(self.territory_count > 0, ErrorCode::InvalidMap);
+        require!(
+            self.adjacency[..self.adjacency_len as usize]
+                .iter()
+                .all(|&adj| u16::from(adj) < self.territory_count),
+            ErrorCode::InvalidMap
+        );
+        Ok(())
+    }
+}
+
+impl TerritoryRecord {
+    pub fn owner(&self) -> Option<Pubkey> {
+        (self.owner != Pubkey::default()).then_some(self.owner)
+    }
+
+    pub fn set_owner(&mut self, owner: Option<Pubkey>) {
+        self.owner = owner.unwrap_or_default();
+    }
+}
+
+// Grows by one player slot per `join_game`
+impl PlayerAccount {
+    pub fn space(player_count: usize, rules: &RuleSet) -> usize {
+        4 + (player_count * PlayerState::space(rules)) + // players vec
+        32 // game pubkey
+    }
+}
+
+impl PlayerState {
+    pub fn new(pubkey: Pubkey, color_id: u8, team_id: Option<u8>) -> Self {
+        Self {
+            pubkey,
+            color_id,
+            cards: Vec::new(),
+            conquered_territory_this_turn: false,
+            mission_commitment: None,
+            capital: None,
+            capital_holder: None,
+            team_id,
+            eliminated: false,
+        }
+    }
+
+    // Hands are capped at `RuleSet::card_limit`, so each slot is sized for a full hand up front
+    pub fn space(rules: &RuleSet) -> usize {
+        32 + // pubkey
+        1 + // color_id
+        4 + (RiskCard::SPACE * rules.card_limit as usize) + // cards vec
+        1 + // conquered_territory_this_turn
+        1 + 32 + // mission_commitment Option
+        1 + 1 + // capital Option
+        1 + 32 + // capital_holder Option
+        1 + 1 + // team_id Option
+        1 // eliminated
+    }
+}
+
+impl RiskCard {
+    pub const SPACE: usize = 1 + // card_type enum
+        1 + 1; // territory_id Option
+}
+
+fn calculate_reinforcements(
+    board: &Board,
+    _player_account: &Account<PlayerAccount>,
+    player: &Pubkey,
+    rules: &RuleSet,
+) -> Result<u16> {
+    let territories_owned = board
+        .territories()
+        .iter()
+        .filter(|t| t.owner() == Some(*player))
+        .count();
+
+    let base_reinforcements = std::cmp::max(
+        u16::try_from(territories_owned / 3).map_err(|_| ErrorCode::TroopOverflow)?,
+        u16::from(rules.min_reinforcements),
+    );
+
+    let reinforcements = base_reinforcements
+        .checked_add(board.continent_bonus(player))
+        .ok_or(ErrorCode::TroopOverflow)?;
+
+    Ok(reinforcements)
+}
+
+// The SPL side of a stake transfer. Only read when `GameConfig::stake_mint` is set, so
+// SOL-staked and free games leave these accounts out.
+struct StakeTokenAccounts<'a, 'info> {
+    // The player's or payee's token account
+    holder: Option<&'a Account<'info, TokenAccount>>,
+    vault: Option<&'a Account<'info, TokenAccount>>,
+    token_program: Option<&'a Program<'info, Token>>,
+}
+
+type RequiredTokenAccounts<'a, 'info> = (
+    &'a Account<'info, TokenAccount>,
+    &'a Account<'info, TokenAccount>,
+    &'a Program<'info, Token>,
+);
+
+impl<'a, 'info> StakeTokenAccounts<'a, 'info> {
+    fn require(&self, mint: &Pubkey) -> Result<RequiredTokenAccounts<'a, 'info>> {
+        match (self.holder, self.vault, self.token_program) {
+            (Some(holder), Some(vault), Some(token_program))
+                if holder.mint == *mint && vault.mint == *mint =>
+            {
+                Ok((holder, vault, token_program))
+            }
+            _ => err!(ErrorCode::StakeAccountMissing),
+        }
+    }
+}
+
+fn deposit_entry_fee<'info>(
+    game: &Game,
+    vault: &mut Account<'info, Vault>,
+    player: &Signer<'info>,
+    tokens: StakeTokenAccounts<'_, 'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let Some(entry_fee) = game.config.entry_fee else {
+        return Ok(());
+    };
+
+    match game.config.stake_mint {
+        Some(mint) => {
+            let (holder, vault_tokens, token_program) = tokens.require(&mint)?;
+            token::transfer(
+                CpiContext::new(
+                    token_program.to_account_info(),
+                    token::Transfer {
+                        from: holder.to_account_info(),
+                        to: vault_tokens.to_account_info(),
+                        authority: player.to_account_info(),
+                    },
+                ),
+                entry_fee,
+            )?;
+        }
+        None => system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: player.to_account_info(),
+                    to: vault.to_account_info(),
+                },
+            ),
+            entry_fee,
+        )?,
+    }
+
+    vault.pot = vault
+        .pot
+        .checked_add(entry_fee)
+        .ok_or(ErrorCode::PotOverflow)?;
+    Ok(())
+}
+
+fn refund_entry_fee<'info>(
+    game: &Account<'info, Game>,
+    vault: &mut Account<'info, Vault>,
+    player: &AccountInfo<'info>,
+    tokens: StakeTokenAccounts<'_, 'info>,
+) -> Result<()> {
+    if let Some(entry_fee) = game.config.entry_fee {
+        let refund = vault.take_refund(entry_fee)?;
+        withdraw_stake(game, vault, refund, player, tokens)?;
+    }
+    Ok(())
+}
+
+// Pays out of the vault: lamports straight to `recipient`, or tokens to the holder
+// account, signed for by the vault PDA
+fn withdraw_stake<'info>(
+    game: &Account<'info, Game>,
+    vault: &Account<'info, Vault>,
+    amount: u64,
+    recipient: &AccountInfo<'info>,
+    tokens: StakeTokenAccounts<'_, 'info>,
+) -> Result<()> {
+    match game.config.stake_mint {
+        Some(mint) => {
+            let (holder, vault_tokens, token_program) = tokens.require(&mint)?;
+            let game_key = game.key();
+            let seeds: &[&[u8]] = &[VAULT_SEED, game_key.as_ref(), &[vault.bump]];
+            token::transfer(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    token::Transfer {
+                        from: vault_tokens.to_account_info(),
+                        to: holder.to_account_info(),
+                        authority: vault.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                amount,
+            )
+        }
+        None => {
+            vault.sub_lamports(amount)?;
+            recipient.add_lamports(amount)?;
+            Ok(())
+        }
+    }
+}
+
+// The vault's token account goes with the vault, its rent back to the creator
+fn close_vault_token_account<'info>(
+    game: &Account<'info, Game>,
+    vault: &Account<'info, Vault>,
+    creator: &AccountInfo<'info>,
+    vault_token_account: Option<&Account<'info, TokenAccount>>,
+    token_program: Option<&Program<'info, Token>>,
+) -> Result<()> {
+    if game.config.stake_mint.is_none() {
+        return Ok(());
+    }
+    let (Some(vault_token_account), Some(token_program)) = (vault_token_account, token_program)
+    else {
+        return err!(ErrorCode::StakeAccountMissing);
+    };
+
+    let game_key = game.key();
+    let seeds: &[&[u8]] = &[VAULT_SEED, game_key.as_ref(), &[vault.bump]];
+    token::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token::CloseAccount {
+            account: vault_token_account.to_account_info(),
+            destination: creator.clone(),
+            authority: vault.to_account_info(),
+        },
+        &[seeds],
+    ))
+}
+
+// Seats owed a cut of the pot. A winning team always splits evenly, otherwise the game's
+// `PrizeSplit` applies. Abandoned games split the pot evenly between the seats still in
+// play, and splits that leave nobody to pay hand every seat its stake back. Rounding dust
+// goes to the first payee.
+pub fn prize_shares(
+    game: &Game,
+    players: &[PlayerState],
+    board: &Board,
+    pot: u64,
+) -> Vec<(usize, u64)> {
+    let seat_of = |pubkey: &Pubkey| players.iter().position(|p| p.pubkey == *pubkey);
+    let mut weights: Vec<(usize, u64)> = if game.state != GameState::Completed {
+        players
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| !p.eliminated)
+            .map(|(seat, _)| (seat, 1))
+            .collect()
+    } else if let Some(team) = game.winning_team {
+        players
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.team_id == Some(team))
+            .map(|(seat, _)| (seat, 1))
+            .collect()
+    } else {
+        match &game.config.prize_split {
+            PrizeSplit::WinnerTakesAll => game
+                .winner
+                .iter()
+                .filter_map(seat_of)
+                .map(|seat| (seat, 1))
+                .collect(),
+            PrizeSplit::Ranked(percentages) => final_ranking(game, board, players)
+                .iter()
+                .filter_map(seat_of)
+                .zip(percentages)
+                .map(|(seat, &percentage)| (seat, u64::from(percentage)))
+                .collect(),
+            PrizeSplit::Territories => players
+                .iter()
+                .enumerate()
+                .map(|(seat, p)| {
+                    let held = board
+                        .territories()
+                        .iter()
+                        .filter(|t| t.owner() == Some(p.pubkey))
+                        .count();
+                    (seat, held as u64)
+                })
+                .collect(),
+        }
+    };
+    weights.retain(|&(_, weight)| weight > 0);
+    if weights.is_empty() {
+        weights = (0..players.len()).map(|seat| (seat, 1)).collect();
+    }
+
+    let total: u64 = weights.iter().map(|&(_, weight)| weight).sum();
+    if total == 0 {
+        return Vec::new();
+    }
+    let mut shares: Vec<(usize, u64)> = weights
+        .iter()
+        .map(|&(seat, weight)| {
+            let share = u128::from(pot) * u128::from(weight) / u128::from(total);
+            (seat, share as u64)
+        })
+        .collect();
+    let dust = pot - shares.iter().map(|&(_, share)| share).sum::<u64>();
+    shares[0].1 += dust;
+    shares
+}
+
+// Standings recorded when the turn limit ended the game, or worked out from the board
+// with the winner placed first
+fn final_ranking(game: &Game, board: &Board, players: &[PlayerState]) -> Vec<Pubkey> {
+    if !game.ranking.is_empty() {
+        return game.ranking.clone();
+    }
+
+    let mut ranking = rank_players(board, players);
+    if let Some(winner) = game.winner {
+        ranking.retain(|p| *p != winner);
+        ranking.insert(0, winner);
+    }
+    ranking
+}
+
+// Probability in parts per million that `rating` beats `opponent`, interpolated
+// from `ELO_EXPECTED_PPM`
+fn expected_score_ppm(rating: i64, opponent: i64) -> i64 {
+    let step = 25 * RATING_SCALE;
+    let diff = (rating - opponent).abs().min(800 * RATING_SCALE);
+    let index = (diff / step) as usize;
+    let next = ELO_EXPECTED_PPM[(index + 1).min(ELO_EXPECTED_PPM.len() - 1)];
+    let ppm = ELO_EXPECTED_PPM[index] + (next - ELO_EXPECTED_PPM[index]) * (diff % step) / step;
+    if rating >= opponent {
+        ppm
+    } else {
+        1_000_000 - ppm
+    }
+}
+
+// Multiplayer Elo: every pair of players is scored as a game between the two, lower
+// place winning and equal places drawing, with K split across the n - 1 opponents.
+// Returns each player's rating change in `RATING_SCALE` units.
+pub fn elo_changes(ratings: &[i64], places: &[usize]) -> Vec<i64> {
+    let opponents = ratings.len().saturating_sub(1) as i64;
+    if opponents == 0 {
+        return vec![0; ratings.len()];
+    }
+
+    (0..ratings.len())
+        .map(|i| {
+            let surprise: i64 = (0..ratings.len())
+                .filter(|&j| j != i)
+                .map(|j| {
+                    let actual = match places[i].cmp(&places[j]) {
+                        std::cmp::Ordering::Less => 1_000_000,
+                        std::cmp::Ordering::Equal => 500_000,
+                        std::cmp::Ordering::Greater => 0,
+                    };
+                    actual - expected_score_ppm(ratings[i], ratings[j])
+                })
+                .sum();
+            ELO_K * RATING_SCALE * surprise / (opponents * 1_000_000)
+        })
+        .collect()
+}
+
+// Rent a joining player paid to grow the player account by one seat
+pub fn player_slot_rent(rent: &Rent, seat: usize, rules: &RuleSet) -> u64 {
+    rent.minimum_balance(8 + PlayerAccount::space(seat + 1, rules))
+        - rent.minimum_balance(8 + PlayerAccount::space(seat, rules))
+}
+
+pub fn validate_game_config(config: &GameConfig) -> Result<()> {
+    let rules = &config.rules;
+    require!(
+        rules.min_reinforcements >= 1
+            && rules.initial_troops >= 1
+            && (1..=RuleSet::MAX_DICE).contains(&rules.max_attack_dice)
+            && (1..=RuleSet::MAX_DICE).contains(&rules.max_defense_dice)
+            && (3..=RuleSet::MAX_CARD_LIMIT).contains(&rules.card_limit)
+            && rules.min_players >= 2
+            && rules.min_players <= rules.max_players
+            && rules.max_players <= RuleSet::MAX_PLAYERS
+            && rules.max_fortifications != Some(0),
+        ErrorCode::InvalidGameConfig
+    );
+    require!(
+        config.max_turns != Some(0) && config.entry_fee != Some(0),
+        ErrorCode::InvalidGameConfig
+    );
+    require!(
+        config.stake_mint.is_none() || config.entry_fee.is_some(),
+        ErrorCode::InvalidGameConfig
+    );
+    require!(
+        (config.victory_mode == VictoryMode::SecretMission) == config.mission_dealer.is_some(),
+        ErrorCode::InvalidGameConfig
+    );
+    if let PrizeSplit::Ranked(percentages) = &config.prize_split {
+        require!(
+            !percentages.is_empty()
+                && percentages.len() <= RuleSet::MAX_PLAYERS as usize
+                && percentages.iter().map(|&p| u32::from(p)).sum::<u32>() == 100,
+            ErrorCode::InvalidGameConfig
+        );
+    }
+
+    if let CardBonusSchedule::Custom(table) = &config.card_bonus_schedule {
+        require!(
+            !table.is_empty() && table.len() <= GameConfig::MAX_CUSTOM_BONUS_STEPS,
+            ErrorCode::InvalidGameConfig
+        );
+    }
+    Ok(())
+}
+
+// Queued players are seated without being asked for a team or a token account, and
+// nobody is around to deal secret missions
+fn validate_queue_config(config: &GameConfig, player_count: u8) -> Result<()> {
+    validate_game_config(config)?;
+    require!(
+        config.stake_mint.is_none()
+            && !config.rules.team_play
+            && config.victory_mode != VictoryMode::SecretMission
+            && (config.rules.min_players..=config.rules.max_players).contains(&player_count),
+        ErrorCode::InvalidGameConfig
+    );
+    Ok(())
 }
 
-#[account]
-pub struct PlayerAccount {
-    pub players: Vec<PlayerState>,
-    pub game: Pubkey,
+// Tables are created and stakes held by the tournament, see `validate_queue_config`
+fn validate_tournament(config: &GameConfig, settings: &TournamentSettings) -> Result<()> {
+    validate_queue_config(config, settings.table_size)?;
+    require!(
+        config.entry_fee.is_none()
+            && settings.entry_fee != Some(0)
+            && (2..=Tournament::MAX_PLAYERS).contains(&settings.max_players)
+            && Tournament::TABLE_SIZES.contains(&settings.table_size)
+            && !settings.prize_split.is_empty()
+            && settings.prize_split.len() <= settings.max_players as usize
+            && settings
+                .prize_split
+                .iter()
+                .map(|&p| u32::from(p))
+                .sum::<u32>()
+                == 100,
+        ErrorCode::InvalidGameConfig
+    );
+    if let TournamentFormat::Swiss { rounds } = settings.format {
+        require!(rounds > 0, ErrorCode::InvalidGameConfig);
+    }
+    Ok(())
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct PlayerState {
-    pub pubkey: Pubkey,
-    pub color_id: u8,
-    pub cards: Vec<RiskCard>,
-    pub conquered_territory_this_turn: bool,
+// Pairs the next round of `tournament` at `now`, or ends it, see `start_round`
+pub fn advance_round(tournament: &mut Tournament, now: i64) -> Result<()> {
+    match tournament.state {
+        TournamentState::Registration => require!(
+            tournament.players.len() >= tournament.settings.prize_split.len(),
+            ErrorCode::NotEnoughPlayers
+        ),
+        TournamentState::InProgress => require!(
+            tournament.tables.iter().all(|t| t.recorded),
+            ErrorCode::RoundNotFinished
+        ),
+        TournamentState::Completed | TournamentState::Cancelled => {
+            return err!(ErrorCode::TournamentFinished)
+        }
+    }
+
+    let mut active: Vec<usize> = (0..tournament.players.len())
+        .filter(|&i| tournament.players[i].eliminated_in_round.is_none())
+        .collect();
+    // The first round needs the ruleset's minimum at every table, and tables differ by
+    // at most one seat
+    let table_size = tournament.settings.table_size as usize;
+    let active_count = active.len();
+    let table_count = active_count.div_ceil(table_size);
+    let tables_fill = table_count > 0
+        && active_count / table_count >= tournament.config.rules.min_players as usize;
+    require!(
+        tables_fill || tournament.state != TournamentState::Registration,
+        ErrorCode::NotEnoughPlayers
+    );
+    // A knockout plays on until one player is left; survivors too few for the ruleset's
+    // minimum still sit down together rather than tie
+    let finished = match tournament.settings.format {
+        TournamentFormat::SingleElimination => active_count <= 1,
+        TournamentFormat::Swiss { rounds } => tournament.round >= rounds,
+    };
+    tournament.tables.clear();
+    tournament.last_activity_at = now;
+    if finished {
+        tournament.state = TournamentState::Completed;
+        msg!("Tournament finished after {} rounds", tournament.round);
+        return Ok(());
+    }
+
+    // Swiss pairs players on similar scores; the sort is stable, so ties keep
+    // registration order
+    if let TournamentFormat::Swiss { .. } = tournament.settings.format {
+        active.sort_by_key(|&i| std::cmp::Reverse(tournament.players[i].points));
+    }
+
+    tournament.state = TournamentState::InProgress;
+    tournament.round += 1;
+    let mut seats = active.into_iter();
+    for table in 0..table_count {
+        // Spread the remainder so table sizes differ by at most one
+        let size = table_seat_count(active_count, table_count, table);
+        tournament.tables.push(TournamentTable {
+            game: Pubkey::default(),
+            seats: seats.by_ref().take(size).map(|i| i as u8).collect(),
+            recorded: false,
+        });
+    }
+    Ok(())
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct Territory {
-    pub id: u8,
-    pub continent_id: u8,
-    pub owner: Option<Pubkey>,
-    pub troops: u8,
-    pub adjacent_territories: Vec<u8>,
+// Seats at `table` when `players` are spread over `tables` as evenly as possible
+fn table_seat_count(players: usize, tables: usize, table: usize) -> usize {
+    players / tables + usize::from(table < players % tables)
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct Continent {
-    pub id: u8,
-    pub territories: Vec<u8>,
-    pub bonus_armies: u8,
+// Final standings as indices into `Tournament::players`: by the round a player went
+// out in for single elimination, then by their place at their last table; by score for
+// Swiss, then by seeding
+pub fn tournament_standings(tournament: &Tournament) -> Vec<usize> {
+    let mut standings: Vec<usize> = (0..tournament.players.len()).collect();
+    standings.sort_by_key(|&i| {
+        let player = &tournament.players[i];
+        match tournament.settings.format {
+            TournamentFormat::SingleElimination => (
+                std::cmp::Reverse(player.eliminated_in_round.map_or(u16::MAX, u16::from)),
+                player.last_place,
+            ),
+            TournamentFormat::Swiss { .. } => (std::cmp::Reverse(player.points), 0),
+        }
+    });
+    standings
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct RiskCard {
-    pub card_type: CardType,
-    pub territory_id: Option<u8>,
+// (player index, lamports) owed to each player: entry fees back from a cancelled
+// tournament, otherwise prizes after the protocol fee
+pub fn tournament_payouts(tournament: &Tournament) -> Vec<(usize, u64)> {
+    if tournament.state == TournamentState::Cancelled {
+        let entry_fee = tournament.settings.entry_fee.unwrap_or(0);
+        return (0..tournament.players.len())
+            .map(|i| (i, entry_fee))
+            .collect();
+    }
+    tournament_prizes(tournament, tournament.pot - tournament.protocol_fee())
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct AttackDice {
-    pub attacker: Vec<u8>,
-    pub defender: Vec<u8>,
+// (player index, lamports) for every player in the prize places; the rounding dust goes
+// to the champion
+fn tournament_prizes(tournament: &Tournament, pool: u64) -> Vec<(usize, u64)> {
+    let mut prizes: Vec<(usize, u64)> = tournament_standings(tournament)
+        .into_iter()
+        .zip(&tournament.settings.prize_split)
+        .map(|(i, &percentage)| (i, (u128::from(pool) * u128::from(percentage) / 100) as u64))
+        .collect();
+    let paid: u64 = prizes.iter().map(|&(_, share)| share).sum();
+    if let Some((_, first)) = prizes.first_mut() {
+        *first += pool - paid;
+    }
+    prizes
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
-pub enum GameState {
-    Setup,
-    InProgress,
-    Completed,
+// Whether two players sit within both of their rating bands
+fn within_bands(a: &QueueEntry, b: &QueueEntry) -> bool {
+    let band = i64::from(a.rating_band.min(b.rating_band)) * RATING_SCALE;
+    (a.rating - b.rating).abs() <= band
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
-pub enum TurnPhase {
-    Reinforcement,
-    Attack,
-    Fortification,
+// First come, first served: the longest-waiting player who can fill a table is matched
+// with the next longest-waiting players who fit everyone already picked. Returns indices
+// into `entries`.
+pub fn pick_match(entries: &[QueueEntry], player_count: usize) -> Option<Vec<usize>> {
+    let mut by_wait: Vec<usize> = (0..entries.len()).collect();
+    by_wait.sort_by_key(|&i| entries[i].enqueued_at);
+
+    by_wait.iter().enumerate().find_map(|(start, &first)| {
+        let mut picked = vec![first];
+        for &candidate in &by_wait[start + 1..] {
+            if picked.len() == player_count {
+                break;
+            }
+            if picked
+                .iter()
+                .all(|&i| within_bands(&entries[i], &entries[candidate]))
+            {
+                picked.push(candidate);
+            }
+        }
+        (picked.len() == player_count).then_some(picked)
+    })
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Hash)]
-pub enum CardType {
-    Infantry,
-    Cavalry,
-    Artillery,
-    Wild,
+// Shuffles matched players with a seed drawn from the match itself, so the order can be
+// checked by anyone but not steered by whoever cranks it
+pub fn seat_order(entries: &[QueueEntry]) -> Vec<Pubkey> {
+    let mut seed_parts: Vec<u8> = Vec::with_capacity(entries.len() * 40);
+    for entry in entries {
+        seed_parts.extend_from_slice(entry.player.as_ref());
+        seed_parts.extend_from_slice(&entry.enqueued_at.to_le_bytes());
+    }
+    shuffle_seats(
+        &seed_parts,
+        entries.iter().map(|entry| entry.player).collect(),
+    )
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
-pub struct TerritoryPlacement {
-    pub territory_id: u8,
-    pub troops: u8,
+// Tournament tables are shuffled the same way, seeded by the tournament, round and table
+pub fn table_seat_order(
+    tournament: &Pubkey,
+    round: u8,
+    table: u8,
+    players: &[Pubkey],
+) -> Vec<Pubkey> {
+    let mut seed_parts = tournament.to_bytes().to_vec();
+    seed_parts.extend_from_slice(&[round, table]);
+    shuffle_seats(&seed_parts, players.to_vec())
 }
 
-#[error_code]
-pub enum ErrorCode {
-    GameAlreadyStarted,
-    GameNotInProgress,
-    NotPlayerTurn,
-    MaxPlayersReached,
-    NotTerritoryOwner,
-    InsufficientTroops,
-    CannotAttackOwnTerritory,
-    InvalidPhase,
-    InvalidCardSet,
-    InvalidDiceCount,
-    TerritoriesNotAdjacent,
-    NoRemainingMoves,
-    InvalidTroopCount,
-    CardLimitReached,
-    ColorAlreadyTaken,
-    NoReinforcements,
-    InvalidCardIndex,
-    TerritoriesNotConnected,
-    InvalidColor,
+fn shuffle_seats(seed_parts: &[u8], mut seats: Vec<Pubkey>) -> Vec<Pubkey> {
+    let seed = anchor_lang::solana_program::hash::hash(seed_parts).to_bytes();
+    seats.sort_by_cached_key(|player| {
+        anchor_lang::solana_program::hash::hashv(&[&seed, player.as_ref()]).to_bytes()
+    });
+    seats
 }
 
-impl Game {
-    pub const SPACE: usize = 32 + // current_player
-        1 + // turn
-        1 + // state enum
-        1 + // current_phase enum
-        1 + // cards_sets_turned_in
-        1 + (10) + // last_attack_dice Option
-        1 + // pending_reinforcements Option
-        32 + // territory_account pubkey
-        32; // player_account pubkey
+fn remove_queue_entry(queue: &mut MatchQueue, player: &Pubkey) -> Result<QueueEntry> {
+    let index = queue
+        .entries
+        .iter()
+        .position(|e| e.player == *player)
+        .ok_or(ErrorCode::NotQueued)?;
+    Ok(queue.entries.remove(index))
 }
 
-impl TerritoryAccount {
-    pub const SPACE: usize = 4 + (50 * 42) + // territories vec (42 territories, ~50 bytes each)
-        4 + (20 * 6) + // continents vec (6 continents, ~20 bytes each)
-        32; // game pubkey
+fn refund_queue_entry<'info>(
+    queue: &Account<'info, MatchQueue>,
+    player: &AccountInfo<'info>,
+) -> Result<()> {
+    if let Some(entry_fee) = queue.config.entry_fee {
+        queue.sub_lamports(entry_fee)?;
+        player.add_lamports(entry_fee)?;
+    }
+    Ok(())
 }
 
-impl PlayerAccount {
-    pub const SPACE: usize = 4 + (100 * 6) + // players vec (max 6 players, ~100 bytes each)
-        32; // game pubkey
+// Shared by `initialize_game` and `match_players`
+fn init_game(
+    game: &mut Game,
+    creator: Pubkey,
+    board: Pubkey,
+    player_account: Pubkey,
+    config: GameConfig,
+    now: i64,
+) {
+    game.creator = creator;
+    game.current_player = creator;
+    game.turn = 1;
+    game.state = GameState::Setup;
+    game.current_phase = TurnPhase::Reinforcement;
+    game.cards_sets_turned_in = 0;
+    game.board = board;
+    game.player_account = player_account;
+    game.config = config;
+    game.winner = None;
+    game.winning_team = None;
+    game.turn_started_at = now;
 }
 
-fn calculate_reinforcements(
-    territory_account: &Account<TerritoryAccount>,
-    _player_account: &Account<PlayerAccount>,
-    player: &Pubkey,
-) -> u8 {
-    let territories_owned = territory_account
-        .territories
+// Seats take colors in order
+fn seat_players(player_account: &mut PlayerAccount, game: Pubkey, players: &[Pubkey]) {
+    player_account.game = game;
+    player_account.players = players
         .iter()
-        .filter(|t| t.owner == Some(*player))
-        .count();
+        .enumerate()
+        .map(|(seat, &pubkey)| PlayerState::new(pubkey, seat as u8, None))
+        .collect();
+}
 
-    let base_reinforcements = std::cmp::max(territories_owned as u8 / 3, 3);
+fn open_listing(
+    listing: &mut Account<GameListing>,
+    bump: u8,
+    game: &Account<Game>,
+    board: &Board,
+    player_count: usize,
+) {
+    listing.game = game.key();
+    listing.creator = game.creator;
+    listing.map_hash = board.map_hash();
+    listing.max_players = game.config.rules.max_players;
+    listing.created_at = game.turn_started_at;
+    listing.entry_fee = game.config.entry_fee;
+    listing.stake_mint = game.config.stake_mint;
+    listing.bump = bump;
+    listing.sync(game, player_count);
+}
 
-    let continent_bonuses = territory_account
-        .continents
-        .iter()
-        .map(|continent| {
-            let owns_continent = continent
-                .territories
-                .iter()
-                .all(|&t_id| territory_account.territories[t_id as usize].owner == Some(*player));
-            if owns_continent {
-                continent.bonus_armies
+// Enough players, on at least two sides in team games, and every secret mission dealt
+fn ready_to_start(game: &Game, players: &[PlayerState]) -> bool {
+    let has_opponents =
+        !game.config.rules.team_play || players.iter().any(|p| p.team_id != players[0].team_id);
+    let missions_dealt = game.config.victory_mode != VictoryMode::SecretMission
+        || players.iter().all(|p| p.mission_commitment.is_some());
+    players.len() >= game.config.rules.min_players as usize && has_opponents && missions_dealt
+}
+
+fn validate_team(rules: &RuleSet, team_id: Option<u8>) -> Result<()> {
+    match team_id {
+        Some(team_id) => require!(
+            rules.team_play && team_id < RuleSet::MAX_PLAYERS,
+            ErrorCode::InvalidTeam
+        ),
+        None => require!(!rules.team_play, ErrorCode::InvalidTeam),
+    }
+    Ok(())
+}
+
+// Armies for turning in `cards` after `sets_turned_in` earlier sets this game
+pub fn card_set_bonus(schedule: &CardBonusSchedule, sets_turned_in: u8, cards: &[RiskCard]) -> u32 {
+    match schedule {
+        CardBonusSchedule::Progressive => match sets_turned_in {
+            0 => 4,
+            1 => 6,
+            2 => 8,
+            3 => 10,
+            4 => 12,
+            n => 15 + 5 * (n as u32 - 5),
+        },
+        CardBonusSchedule::FixedValue => {
+            // A wild can always complete one of each, which is worth the most
+            if cards.iter().all(|c| c.card_type == cards[0].card_type) {
+                match cards[0].card_type {
+                    CardType::Infantry => 4,
+                    CardType::Cavalry => 6,
+                    CardType::Artillery => 8,
+                    CardType::Wild => 10,
+                }
             } else {
-                0
+                10
             }
-        })
-        .sum::<u8>();
-
-    base_reinforcements + continent_bonuses
+        }
+        CardBonusSchedule::Custom(table) => table
+            .get(sets_turned_in as usize)
+            .or(table.last())
+            .copied()
+            .unwrap_or(0) as u32,
+    }
 }
 
 fn roll_dice(count: u8) -> Vec<u8> {
@@ -672,7 +4351,7 @@ fn roll_dice(count: u8) -> Vec<u8> {
     dice
 }
 
-fn resolve_combat(attacker_dice: &[u8], defender_dice: &[u8]) -> (u8, u8) {
+fn resolve_combat(attacker_dice: &[u8], defender_dice: &[u8]) -> (u16, u16) {
     let mut attacker_losses = 0;
     let mut defender_losses = 0;
 
@@ -702,49 +4381,288 @@ fn generate_risk_card() -> RiskCard {
     }
 }
 
-fn check_victory_condition(
-    game: &mut Account<Game>,
-    territory_state: &Account<territory::TerritoryState>,
-) {
-    let first_owner = territory_state.territories[0].owner;
-    if first_owner.is_some()
-        && territory_state
-            .territories
+// Ends the game if one player or team has won by the rules in play
+pub fn check_victory_condition(game: &mut Game, board: &Board, players: &[PlayerState]) {
+    if let Some(last) = last_player_standing(players) {
+        game.state = GameState::Completed;
+        game.winner = Some(last);
+        return;
+    }
+
+    // Neutral territories (no owner) don't need to be conquered
+    let mut owners = board.territories().iter().filter_map(|t| t.owner());
+    let first_owner = owners.next();
+    if first_owner.is_some() && owners.all(|owner| Some(owner) == first_owner) {
+        game.state = GameState::Completed;
+        game.winner = first_owner;
+        return;
+    }
+
+    if game.config.rules.team_play {
+        let team_of = |owner: Pubkey| {
+            players
+                .iter()
+                .find(|p| p.pubkey == owner)
+                .and_then(|p| p.team_id)
+        };
+        let mut teams = board
+            .territories()
             .iter()
-            .all(|t| t.owner == first_owner)
+            .filter_map(|t| t.owner())
+            .map(team_of);
+        if let Some(Some(first_team)) = teams.next() {
+            if teams.all(|team| team == Some(first_team)) {
+                game.state = GameState::Completed;
+                game.winning_team = Some(first_team);
+                return;
+            }
+        }
+    }
+
+    if game.config.victory_mode == VictoryMode::Capital {
+        if let Some(winner) = capital_winner(players) {
+            game.state = GameState::Completed;
+            game.winner = Some(winner);
+        }
+    }
+}
+
+// Taking a player's last territory knocks them out. Returns whether `defender` went
+// out just now.
+pub fn knock_out_if_wiped_out(
+    board: &Board,
+    players: &mut [PlayerState],
+    defender: &Pubkey,
+) -> bool {
+    if board
+        .territories()
+        .iter()
+        .any(|t| t.owner() == Some(*defender))
     {
-        game.state = GameState::Completed;
+        return false;
+    }
+    match players
+        .iter_mut()
+        .find(|p| p.pubkey == *defender && !p.eliminated)
+    {
+        Some(state) => {
+            state.eliminated = true;
+            true
+        }
+        None => false,
+    }
+}
+
+// The only seat neither resigned nor conquered, if it has come to that
+pub fn last_player_standing(players: &[PlayerState]) -> Option<Pubkey> {
+    let mut remaining = players.iter().filter(|p| !p.eliminated);
+    match (remaining.next(), remaining.next()) {
+        (Some(last), None) => Some(last.pubkey),
+        _ => None,
+    }
+}
+
+// Hands any capital on `territory_id` to `holder`, returning the player it belongs to
+pub fn capture_capital(
+    players: &mut [PlayerState],
+    territory_id: u8,
+    holder: Option<Pubkey>,
+) -> Option<Pubkey> {
+    let owner = players
+        .iter_mut()
+        .find(|p| p.capital == Some(territory_id))?;
+    owner.capital_holder = holder;
+    Some(owner.pubkey)
+}
+
+// The player holding every capital, once all of them have been chosen
+pub fn capital_winner(players: &[PlayerState]) -> Option<Pubkey> {
+    let mut holders = players.iter().map(|p| p.capital.and(p.capital_holder));
+    let first = holders.next()??;
+    holders.all(|holder| holder == Some(first)).then_some(first)
+}
+
+// The dealer hands each player their mission and salt off-chain and commits
+// sha256(player || mission || salt), so nobody can read the mission from the account.
+fn mission_commitment(player: &Pubkey, mission: &Mission, salt: &[u8; 32]) -> Result<[u8; 32]> {
+    let mission_bytes = mission.try_to_vec()?;
+    Ok(
+        anchor_lang::solana_program::hash::hashv(&[player.as_ref(), &mission_bytes, salt])
+            .to_bytes(),
+    )
+}
+
+fn is_mission_complete(
+    mission: &Mission,
+    claimant: &Pubkey,
+    board: &Board,
+    player_account: &Account<PlayerAccount>,
+) -> bool {
+    let owned_count = |min_troops: u8| {
+        board
+            .territories()
+            .iter()
+            .filter(|t| t.owner() == Some(*claimant) && t.troops >= u16::from(min_troops))
+            .count()
+    };
+
+    match mission {
+        Mission::ConquerContinents(continent_ids) => continent_ids
+            .iter()
+            .all(|&continent_id| board.owns_continent(continent_id, claimant)),
+        Mission::EliminateColor {
+            color_id,
+            fallback_territories,
+        } => match player_account
+            .players
+            .iter()
+            .find(|p| p.color_id == *color_id && p.pubkey != *claimant)
+        {
+            Some(target) => !board
+                .territories()
+                .iter()
+                .any(|t| t.owner() == Some(target.pubkey)),
+            None => owned_count(1) >= *fallback_territories as usize,
+        },
+        Mission::HoldTerritories { count, min_troops } => {
+            owned_count(*min_troops) >= *count as usize
+        }
+    }
+}
+
+// Knocks `player` out, handing their territories and any capitals they hold to
+// `heir` (neutral if none), and passes the turn on at `now` if it was theirs
+pub fn forfeit(
+    game: &mut Game,
+    board: &mut Board,
+    players: &mut [PlayerState],
+    player: Pubkey,
+    heir: Option<Pubkey>,
+    now: i64,
+) -> Result<()> {
+    let player_state = players
+        .iter_mut()
+        .find(|p| p.pubkey == player)
+        .ok_or(ErrorCode::PlayerNotFound)?;
+    require!(!player_state.eliminated, ErrorCode::PlayerEliminated);
+    player_state.eliminated = true;
+    player_state.cards.clear();
+    player_state.conquered_territory_this_turn = false;
+
+    let territory_count = board.territory_count as usize;
+    for territory in board.territories[..territory_count]
+        .iter_mut()
+        .filter(|t| t.owner() == Some(player))
+    {
+        territory.set_owner(heir);
+    }
+    // Capitals go with the territories they sit on
+    for p in players
+        .iter_mut()
+        .filter(|p| p.capital_holder == Some(player))
+    {
+        p.capital_holder = heir;
+    }
+
+    if game.current_player == player {
+        game.pending_reinforcements = None;
+        game.current_phase = TurnPhase::Reinforcement;
+        next_turn(game, players, board, now);
+    }
+
+    if game.state == GameState::InProgress {
+        check_victory_condition(game, board, players);
     }
+    Ok(())
 }
 
-fn next_turn(game: &mut Account<Game>, player_account: &Account<PlayerAccount>) {
-    let current_player_index = player_account
-        .players
+// Passes the turn to the next seat still in play at `now`; past `GameConfig::max_turns`
+// the game ends with every player ranked instead
+pub fn next_turn(game: &mut Game, players: &[PlayerState], board: &Board, now: i64) {
+    let current_player_index = players
         .iter()
         .position(|p| p.pubkey == game.current_player)
         .unwrap();
 
-    let next_player_index = (current_player_index + 1) % player_account.players.len();
-    game.current_player = player_account.players[next_player_index].pubkey;
-    game.turn += 1;
+    // Resigned players are skipped
+    let player_count = players.len();
+    let next_player_index = (1..=player_count)
+        .map(|offset| (current_player_index + offset) % player_count)
+        .find(|&i| !players[i].eliminated)
+        .unwrap_or(current_player_index);
+    game.current_player = players[next_player_index].pubkey;
+    game.turn = game.turn.saturating_add(1);
+    game.fortifications_this_turn = 0;
+    game.turn_started_at = now;
+
+    if let Some(max_turns) = game.config.max_turns {
+        if game.turn > max_turns {
+            game.ranking = rank_players(board, players);
+            game.winner = game.ranking.first().copied();
+            game.state = GameState::Completed;
+        }
+    }
 }
 
-fn distribute_territories(
-    territory_account: &mut Account<TerritoryAccount>,
-    player_account: &Account<PlayerAccount>,
-) {
-    let seed: Vec<u8> = player_account
-        .players
+// Orders players by territories held, then total troops, then continent bonuses.
+// Ties keep seating order.
+fn rank_players(board: &Board, players: &[PlayerState]) -> Vec<Pubkey> {
+    let mut scores: Vec<(Pubkey, (usize, u32, u16))> = players
         .iter()
-        .flat_map(|p| p.pubkey.to_bytes())
+        .map(|p| {
+            let owned = || {
+                board
+                    .territories()
+                    .iter()
+                    .filter(|t| t.owner() == Some(p.pubkey))
+            };
+            (
+                p.pubkey,
+                (
+                    owned().count(),
+                    owned().map(|t| t.troops as u32).sum(),
+                    board.continent_bonus(&p.pubkey),
+                ),
+            )
+        })
         .collect();
 
-    let player_count = player_account.players.len();
+    scores.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    scores.into_iter().map(|(pubkey, _)| pubkey).collect()
+}
+
+// Deals every territory to a seat with `RuleSet::initial_troops`, seeded by who is playing
+// Deals the board before the first turn is played, never again: a second deal would
+// reshuffle territories mid-game and strand capitals with their old holders
+pub fn deal(game: &mut Game, board: &mut Board, players: &[PlayerState]) -> Result<()> {
+    require!(
+        !game.dealt
+            && game.turn == 1
+            && game.current_phase == TurnPhase::Reinforcement
+            && game.pending_reinforcements.is_none(),
+        ErrorCode::AlreadyDealt
+    );
+    distribute_territories(board, players, &game.config.rules);
+    game.dealt = true;
+    Ok(())
+}
+
+pub fn distribute_territories(board: &mut Board, players: &[PlayerState], rules: &RuleSet) {
+    let seed: Vec<u8> = players.iter().flat_map(|p| p.pubkey.to_bytes()).collect();
+
+    let player_count = players.len();
+    // Two-player games deal a third share to the neutral army
+    let share_count = if rules.neutral_army && player_count == 2 {
+        3
+    } else {
+        player_count
+    };
 
-    for (i, territory) in territory_account.territories.iter_mut().enumerate() {
-        let player_index = (hash_to_index(&seed, i) % player_count) as usize;
-        territory.owner = Some(player_account.players[player_index].pubkey);
-        territory.troops = 3; // Initial troops per territory
+    let territory_count = board.territory_count as usize;
+    for (i, territory) in board.territories[..territory_count].iter_mut().enumerate() {
+        let player_index = hash_to_index(&seed, i) % share_count;
+        territory.set_owner(players.get(player_index).map(|p| p.pubkey));
+        territory.troops = u16::from(rules.initial_troops);
     }
 }
 
@@ -790,13 +4708,220 @@ fn is_valid_card_set(cards: &[RiskCard]) -> bool {
     false
 }
 
-fn are_territories_connected(
-    territory_account: &Account<TerritoryAccount>,
-    start: u8,
-    end: u8,
-    owner: &Pubkey,
-) -> bool {
-    let mut visited = vec![false; territory_account.territories.len()];
+// Orders a pair of players so both sides derive the same treaty address
+pub fn treaty_parties(a: &Pubkey, b: &Pubkey) -> (Pubkey, Pubkey) {
+    if a < b {
+        (*a, *b)
+    } else {
+        (*b, *a)
+    }
+}
+
+// Attacks on another player must pass the pair's treaty PDA among the remaining
+// accounts, initialized or not, so a truce can't be dodged by leaving it out.
+fn is_truce_active(
+    game: &Account<Game>,
+    attacker: &Pubkey,
+    defender: &Pubkey,
+    remaining_accounts: &[AccountInfo],
+) -> Result<bool> {
+    let (first, second) = treaty_parties(attacker, defender);
+    let (treaty_key, _) = Pubkey::find_program_address(
+        &[
+            TREATY_SEED,
+            game.key().as_ref(),
+            first.as_ref(),
+            second.as_ref(),
+        ],
+        &crate::ID,
+    );
+
+    let treaty_info = remaining_accounts
+        .iter()
+        .find(|a| a.key() == treaty_key)
+        .ok_or(ErrorCode::TreatyAccountMissing)?;
+    if treaty_info.data_is_empty() {
+        return Ok(false);
+    }
+
+    require_keys_eq!(
+        *treaty_info.owner,
+        crate::ID,
+        ErrorCode::TreatyAccountMissing
+    );
+    let treaty = Treaty::try_deserialize(&mut &treaty_info.try_borrow_data()?[..])?;
+    Ok(treaty.is_active(game.turn))
+}
+
+// Places exactly `reinforcements` troops on `player`'s territories, checking every
+// placement before any troops land
+pub fn place_troops(
+    board: &mut Board,
+    player: &Pubkey,
+    placements: Vec<TerritoryPlacement>,
+    reinforcements: u16,
+) -> Result<()> {
+    // Validate total troops being placed matches reinforcements
+    let placements = coalesce_placements(placements)?;
+    let total_troops = placements.iter().try_fold(0u16, |total, p| {
+        total.checked_add(p.troops).ok_or(ErrorCode::TroopOverflow)
+    })?;
+    require!(total_troops == reinforcements, ErrorCode::InvalidTroopCount);
+
+    for placement in &placements {
+        let territory = board.territory(placement.territory_id)?;
+        require!(
+            territory.owner() == Some(*player),
+            ErrorCode::NotTerritoryOwner
+        );
+        territory
+            .troops
+            .checked_add(placement.troops)
+            .ok_or(ErrorCode::TroopOverflow)?;
+    }
+
+    for placement in placements {
+        board.territory_mut(placement.territory_id)?.troops += placement.troops;
+    }
+    Ok(())
+}
+
+// Merges placements on the same territory so each is validated once
+fn coalesce_placements(placements: Vec<TerritoryPlacement>) -> Result<Vec<TerritoryPlacement>> {
+    let mut coalesced: Vec<TerritoryPlacement> = Vec::with_capacity(placements.len());
+    for placement in placements {
+        match coalesced
+            .iter_mut()
+            .find(|p| p.territory_id == placement.territory_id)
+        {
+            Some(existing) => {
+                existing.troops = existing
+                    .troops
+                    .checked_add(placement.troops)
+                    .ok_or(ErrorCode::TroopOverflow)?;
+            }
+            None => coalesced.push(placement),
+        }
+    }
+    Ok(coalesced)
+}
+
+// Repeated moves between the same two territories count as a single move
+pub fn coalesce_fortify_moves(moves: Vec<FortifyMove>) -> Result<Vec<FortifyMove>> {
+    let mut coalesced: Vec<FortifyMove> = Vec::with_capacity(moves.len());
+    for fortify_move in moves {
+        match coalesced.iter_mut().find(|m| {
+            m.from_territory == fortify_move.from_territory
+                && m.to_territory == fortify_move.to_territory
+        }) {
+            Some(existing) => {
+                existing.troops = existing
+                    .troops
+                    .checked_add(fortify_move.troops)
+                    .ok_or(ErrorCode::TroopOverflow)?;
+            }
+            None => coalesced.push(fortify_move),
+        }
+    }
+    Ok(coalesced)
+}
+
+// Moves troops from one of `player`'s territories to any territory an ally holds, within
+// the game's fortification rules
+pub fn apply_fortify_move(
+    game: &mut Game,
+    board: &mut Board,
+    player: &Pubkey,
+    allies: &[Pubkey],
+    fortify_move: &FortifyMove,
+) -> Result<()> {
+    let &FortifyMove {
+        from_territory,
+        to_territory,
+        troops,
+    } = fortify_move;
+
+    require!(
+        from_territory != to_territory && troops > 0,
+        ErrorCode::InvalidTroopCount
+    );
+    if let Some(max_fortifications) = game.config.rules.max_fortifications {
+        require!(
+            game.fortifications_this_turn < max_fortifications,
+            ErrorCode::FortificationLimitReached
+        );
+    }
+
+    // Validate territory ownership and troops
+    let from_territory_ref = board.territory(from_territory)?;
+    require!(
+        from_territory_ref.owner() == Some(*player),
+        ErrorCode::NotTerritoryOwner
+    );
+    require!(
+        from_territory_ref.troops > troops,
+        ErrorCode::InsufficientTroops
+    );
+
+    // Troops may be moved onto and through teammates' territories
+    let to_territory_ref = board.territory(to_territory)?;
+    require!(
+        to_territory_ref
+            .owner()
+            .is_some_and(|owner| allies.contains(&owner)),
+        ErrorCode::NotTerritoryOwner
+    );
+    to_territory_ref
+        .troops
+        .checked_add(troops)
+        .ok_or(ErrorCode::TroopOverflow)?;
+
+    // Validate connectivity
+    if game.config.rules.adjacent_fortify_only {
+        require!(
+            board
+                .adjacent_territories(from_territory_ref)
+                .contains(&to_territory),
+            ErrorCode::TerritoriesNotAdjacent
+        );
+    } else {
+        let are_connected = are_territories_connected(board, from_territory, to_territory, allies);
+        require!(are_connected, ErrorCode::TerritoriesNotConnected);
+    }
+
+    // Move troops
+    board.territory_mut(from_territory)?.troops -= troops;
+    board.territory_mut(to_territory)?.troops += troops;
+    game.fortifications_this_turn = game.fortifications_this_turn.saturating_add(1);
+
+    Ok(())
+}
+
+fn are_allies(players: &[PlayerState], a: &Pubkey, b: &Pubkey) -> bool {
+    allies_of(players, a).contains(b)
+}
+
+// The player plus, in team games, everyone sharing their team
+pub fn allies_of(players: &[PlayerState], player: &Pubkey) -> Vec<Pubkey> {
+    let team_id = players
+        .iter()
+        .find(|p| p.pubkey == *player)
+        .and_then(|p| p.team_id);
+
+    let mut allies = vec![*player];
+    if team_id.is_some() {
+        allies.extend(
+            players
+                .iter()
+                .filter(|p| p.pubkey != *player && p.team_id == team_id)
+                .map(|p| p.pubkey),
+        );
+    }
+    allies
+}
+
+fn are_territories_connected(board: &Board, start: u8, end: u8, owners: &[Pubkey]) -> bool {
+    let mut visited = [false; MAX_TERRITORIES];
     let mut stack = vec![start];
     visited[start as usize] = true;
 
@@ -805,9 +4930,11 @@ fn are_territories_connected(
             return true;
         }
 
-        for &adj in &territory_account.territories[current as usize].adjacent_territories {
+        for &adj in board.adjacent_territories(&board.territories[current as usize]) {
             if !visited[adj as usize]
-                && territory_account.territories[adj as usize].owner == Some(*owner)
+                && board.territories[adj as usize]
+                    .owner()
+                    .is_some_and(|owner| owners.contains(&owner))
             {
                 visited[adj as usize] = true;
                 stack.push(adj);
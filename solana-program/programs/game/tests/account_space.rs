@@ -0,0 +1,474 @@
+use ::risk_game::*;
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+fn worst_case_config() -> GameConfig {
+    GameConfig {
+        card_bonus_schedule: CardBonusSchedule::Custom(vec![
+            u8::MAX;
+            GameConfig::MAX_CUSTOM_BONUS_STEPS
+        ]),
+        rules: RuleSet {
+            max_attack_dice: RuleSet::MAX_DICE,
+            max_defense_dice: RuleSet::MAX_DICE,
+            card_limit: RuleSet::MAX_CARD_LIMIT,
+            max_players: RuleSet::MAX_PLAYERS,
+            max_fortifications: Some(u8::MAX),
+            ..RuleSet::default()
+        },
+        victory_mode: VictoryMode::Capital,
+        max_turns: Some(u32::MAX),
+        entry_fee: Some(u64::MAX),
+        stake_mint: Some(Pubkey::new_unique()),
+        prize_split: PrizeSplit::Ranked(vec![u8::MAX; RuleSet::MAX_PLAYERS as usize]),
+        mission_dealer: Some(Pubkey::new_unique()),
+    }
+}
+
+fn map(
+    territory_count: u8,
+    adjacency: u8,
+    continent_count: u8,
+) -> (Vec<Territory>, Vec<Continent>) {
+    let territories = (0..territory_count)
+        .map(|id| Territory {
+            id,
+            continent_id: id % continent_count,
+            owner: Some(Pubkey::new_unique()),
+            troops: u16::MAX,
+            adjacent_territories: (0..adjacency)
+                .map(|offset| id.wrapping_add(offset + 1) % territory_count)
+                .collect(),
+        })
+        .collect();
+    let continents = (0..continent_count)
+        .map(|id| Continent {
+            id,
+            territories: (0..territory_count)
+                .filter(|t| t % continent_count == id)
+                .collect(),
+            bonus_armies: u8::MAX,
+        })
+        .collect();
+    (territories, continents)
+}
+
+fn full_player(rules: &RuleSet, color_id: u8) -> PlayerState {
+    PlayerState {
+        pubkey: Pubkey::new_unique(),
+        color_id,
+        cards: vec![
+            RiskCard {
+                card_type: CardType::Wild,
+                territory_id: Some(u8::MAX),
+            };
+            rules.card_limit as usize
+        ],
+        conquered_territory_this_turn: true,
+        mission_commitment: Some([u8::MAX; 32]),
+        capital: Some(u8::MAX),
+        capital_holder: Some(Pubkey::new_unique()),
+        team_id: Some(color_id),
+        eliminated: true,
+    }
+}
+
+#[test]
+fn game_fits_worst_case_state() {
+    let config = worst_case_config();
+    let rules = config.rules.clone();
+    let game = Game {
+        creator: Pubkey::new_unique(),
+        current_player: Pubkey::new_unique(),
+        turn: u32::MAX,
+        state: GameState::Completed,
+        current_phase: TurnPhase::Fortification,
+        cards_sets_turned_in: u8::MAX,
+        last_attack_dice: Some(AttackDice {
+            attacker: vec![6; rules.max_attack_dice as usize],
+            defender: vec![6; rules.max_defense_dice as usize],
+        }),
+        pending_reinforcements: Some(u16::MAX),
+        board: Pubkey::new_unique(),
+        player_account: Pubkey::new_unique(),
+        config: config.clone(),
+        winner: Some(Pubkey::new_unique()),
+        ranking: (0..rules.max_players)
+            .map(|_| Pubkey::new_unique())
+            .collect(),
+        winning_team: Some(0),
+        fortifications_this_turn: u8::MAX,
+        turn_started_at: i64::MAX,
+        stats_recorded: true,
+        lobby: true,
+        dealt: true,
+    };
+
+    assert_eq!(game.try_to_vec().unwrap().len(), Game::space(&config));
+}
+
+#[test]
+fn game_space_follows_config() {
+    let classic = GameConfig {
+        card_bonus_schedule: CardBonusSchedule::Progressive,
+        rules: RuleSet::default(),
+        victory_mode: VictoryMode::WorldDomination,
+        max_turns: None,
+        entry_fee: None,
+        stake_mint: None,
+        prize_split: PrizeSplit::WinnerTakesAll,
+        mission_dealer: None,
+    };
+
+    assert!(Game::space(&classic) < Game::space(&worst_case_config()));
+}
+
+// Clients create the board themselves, see tests/token-stakes.ts
+#[test]
+fn board_size_is_stable() {
+    assert_eq!(Board::SPACE, 13926);
+}
+
+#[test]
+fn board_loads_large_map_in_chunks() {
+    let (territories, continents) = map(255, 12, 20);
+    let mut board: Board = bytemuck::Zeroable::zeroed();
+    for chunk in territories.chunks(32) {
+        board.append_map(chunk, &continents).unwrap();
+    }
+    board.validate_map().unwrap();
+
+    assert_eq!(board.territories().len(), 255);
+    assert_eq!(board.continent_count, 20);
+    for territory in &territories {
+        let record = board.territory(territory.id).unwrap();
+        assert_eq!(record.owner(), territory.owner);
+        assert_eq!(record.troops, territory.troops);
+        assert_eq!(record.continent_id, territory.continent_id);
+        assert_eq!(
+            board.adjacent_territories(record),
+            &territory.adjacent_territories[..]
+        );
+    }
+    assert!(board.territory(255).is_err());
+}
+
+#[test]
+fn map_hash_ignores_who_holds_the_map() {
+    let (territories, continents) = map(12, 2, 3);
+    let map_hash = |territories: &[Territory]| {
+        let mut board: Board = bytemuck::Zeroable::zeroed();
+        board.append_map(territories, &continents).unwrap();
+        board.map_hash()
+    };
+
+    let mut held = territories.clone();
+    held[3].owner = None;
+    held[3].troops = 7;
+    assert_eq!(map_hash(&held), map_hash(&territories));
+
+    let mut rewired = territories.clone();
+    rewired[0].adjacent_territories[0] = 5;
+    assert_ne!(map_hash(&rewired), map_hash(&territories));
+}
+
+#[test]
+fn board_rejects_maps_past_capacity() {
+    let mut board: Board = bytemuck::Zeroable::zeroed();
+    let (territories, continents) = map(255, 17, 1);
+    assert_eq!(
+        board.append_map(&territories, &continents).unwrap_err(),
+        ::risk_game::ErrorCode::MapTooLarge.into()
+    );
+
+    // Ids must continue where the previous chunk stopped
+    let mut board: Board = bytemuck::Zeroable::zeroed();
+    let (territories, continents) = map(4, 1, 1);
+    assert_eq!(
+        board
+            .append_map(&territories[1..], &continents)
+            .unwrap_err(),
+        ::risk_game::ErrorCode::InvalidMap.into()
+    );
+
+    // Neighbours pointing past the last territory are caught before play starts
+    let mut board: Board = bytemuck::Zeroable::zeroed();
+    board.append_map(&territories[..2], &continents).unwrap();
+    assert_eq!(
+        board.validate_map().unwrap_err(),
+        ::risk_game::ErrorCode::InvalidMap.into()
+    );
+}
+
+#[test]
+fn player_account_fits_full_hands_at_every_size() {
+    let rules = worst_case_config().rules;
+    for player_count in 1..=rules.max_players {
+        let account = PlayerAccount {
+            players: (0..player_count).map(|i| full_player(&rules, i)).collect(),
+            game: Pubkey::new_unique(),
+        };
+
+        assert_eq!(
+            account.try_to_vec().unwrap().len(),
+            PlayerAccount::space(player_count as usize, &rules)
+        );
+    }
+}
+
+#[test]
+fn treaty_fits_broken_truce() {
+    let treaty = Treaty {
+        game: Pubkey::new_unique(),
+        proposer: Pubkey::new_unique(),
+        target: Pubkey::new_unique(),
+        turns: u32::MAX,
+        expires_at_turn: u32::MAX,
+        status: TreatyStatus::Broken,
+        broken_by: Some(Pubkey::new_unique()),
+        bump: u8::MAX,
+    };
+
+    assert_eq!(treaty.try_to_vec().unwrap().len(), Treaty::SPACE);
+}
+
+#[test]
+fn game_result_fits_full_ranking() {
+    let rules = worst_case_config().rules;
+    let result = GameResult {
+        game: Pubkey::new_unique(),
+        creator: Pubkey::new_unique(),
+        winner: Some(Pubkey::new_unique()),
+        winning_team: Some(0),
+        ranking: (0..rules.max_players)
+            .map(|_| Pubkey::new_unique())
+            .collect(),
+        turns: u32::MAX,
+        abandoned: true,
+        closed_at: i64::MAX,
+        bump: u8::MAX,
+    };
+
+    assert_eq!(
+        result.try_to_vec().unwrap().len(),
+        GameResult::space(&rules)
+    );
+}
+
+#[test]
+fn vault_fits_settled_pot() {
+    let vault = Vault {
+        game: Pubkey::new_unique(),
+        pot: u64::MAX,
+        paid_out: u64::MAX,
+        protocol_fee_bps: u16::MAX,
+        fee_collected: true,
+        claimed_seats: u8::MAX,
+        bump: u8::MAX,
+    };
+
+    assert_eq!(vault.try_to_vec().unwrap().len(), Vault::SPACE);
+}
+
+#[test]
+fn player_profile_size_is_exact() {
+    let profile = PlayerProfile {
+        player: Pubkey::new_unique(),
+        games_played: u32::MAX,
+        wins: u32::MAX,
+        eliminations: u32::MAX,
+        territories_conquered: u32::MAX,
+        dice_rolled: u64::MAX,
+        games_per_color: [u32::MAX; 6],
+        favorite_color: Some(u8::MAX),
+        ratings: [PoolRating {
+            pool: [u8::MAX; 32],
+            rating: i32::MAX,
+            games: u32::MAX,
+        }; RATING_POOLS],
+        bump: u8::MAX,
+    };
+
+    assert_eq!(profile.try_to_vec().unwrap().len(), PlayerProfile::SPACE);
+}
+
+#[test]
+fn config_size_is_exact() {
+    let config = Config {
+        admin: Pubkey::new_unique(),
+        treasury: Pubkey::new_unique(),
+        protocol_fee_bps: Config::MAX_PROTOCOL_FEE_BPS,
+        bump: u8::MAX,
+        resumed_at: i64::MAX,
+        paused: true,
+    };
+
+    assert_eq!(config.try_to_vec().unwrap().len(), Config::SPACE);
+}
+
+#[test]
+fn config_paused_flag_is_where_other_programs_read_it() {
+    let config = Config {
+        admin: Pubkey::new_unique(),
+        treasury: Pubkey::new_unique(),
+        protocol_fee_bps: 0,
+        bump: 0,
+        resumed_at: 0,
+        paused: true,
+    };
+    let mut data = Config::DISCRIMINATOR.to_vec();
+    data.extend(config.try_to_vec().unwrap());
+
+    assert_eq!(territory::risk_game::ID, ::risk_game::ID);
+    assert_eq!(
+        territory::risk_game::CONFIG_DISCRIMINATOR,
+        Config::DISCRIMINATOR
+    );
+    assert_eq!(data[territory::risk_game::CONFIG_PAUSED_OFFSET], 1);
+    assert_eq!(territory::risk_game::CONFIG_PAUSED_OFFSET, data.len() - 1);
+}
+
+#[test]
+fn game_state_is_where_other_programs_read_it() {
+    let config = worst_case_config();
+    let game = Game {
+        creator: Pubkey::new_unique(),
+        current_player: Pubkey::new_unique(),
+        turn: u32::MAX,
+        state: GameState::Aborted,
+        current_phase: TurnPhase::Reinforcement,
+        cards_sets_turned_in: 0,
+        last_attack_dice: None,
+        pending_reinforcements: None,
+        board: Pubkey::new_unique(),
+        player_account: Pubkey::new_unique(),
+        config,
+        winner: None,
+        ranking: Vec::new(),
+        winning_team: None,
+        fortifications_this_turn: 0,
+        turn_started_at: 0,
+        stats_recorded: false,
+        lobby: false,
+        dealt: false,
+    };
+    let mut data = Game::DISCRIMINATOR.to_vec();
+    data.extend(game.try_to_vec().unwrap());
+
+    assert_eq!(
+        territory::risk_game::GAME_DISCRIMINATOR,
+        Game::DISCRIMINATOR
+    );
+    assert_eq!(
+        data[territory::risk_game::GAME_STATE_OFFSET],
+        GameState::Aborted.try_to_vec().unwrap()[0]
+    );
+    let terminal: Vec<u8> = [
+        GameState::Completed,
+        GameState::Aborted,
+        GameState::Abandoned,
+    ]
+    .iter()
+    .map(|state| state.try_to_vec().unwrap()[0])
+    .collect();
+    assert_eq!(
+        territory::risk_game::GAME_FINISHED_STATES,
+        terminal.as_slice()
+    );
+}
+
+#[test]
+fn game_listing_size_and_state_offset() {
+    let listing = GameListing {
+        state: GameState::InProgress,
+        player_count: u8::MAX,
+        max_players: u8::MAX,
+        game: Pubkey::new_unique(),
+        creator: Pubkey::new_unique(),
+        map_hash: [u8::MAX; 32],
+        created_at: i64::MAX,
+        bump: u8::MAX,
+        entry_fee: Some(u64::MAX),
+        stake_mint: Some(Pubkey::new_unique()),
+    };
+    let mut data = GameListing::DISCRIMINATOR.to_vec();
+    data.extend(listing.try_to_vec().unwrap());
+
+    assert_eq!(data.len(), 8 + GameListing::SPACE);
+    assert_eq!(
+        data[GameListing::STATE_OFFSET],
+        GameState::InProgress.try_to_vec().unwrap()[0]
+    );
+}
+
+#[test]
+fn full_match_queue_fits_allocated_space() {
+    let config = worst_case_config();
+    let queue = MatchQueue {
+        creator: Pubkey::new_unique(),
+        config: config.clone(),
+        player_count: RuleSet::MAX_PLAYERS,
+        template: Pubkey::new_unique(),
+        rating_pool: [u8::MAX; 32],
+        entries: vec![
+            QueueEntry {
+                player: Pubkey::new_unique(),
+                rating: i64::MAX,
+                rating_band: u32::MAX,
+                enqueued_at: i64::MAX,
+            };
+            MatchQueue::MAX_ENTRIES
+        ],
+    };
+
+    assert_eq!(
+        queue.try_to_vec().unwrap().len(),
+        MatchQueue::space(&config)
+    );
+}
+
+#[test]
+fn full_tournament_fits_allocated_space() {
+    let config = worst_case_config();
+    let settings = TournamentSettings {
+        format: TournamentFormat::Swiss { rounds: u8::MAX },
+        table_size: RuleSet::MAX_PLAYERS,
+        max_players: Tournament::MAX_PLAYERS,
+        entry_fee: Some(u64::MAX),
+        prize_split: vec![u8::MAX; Tournament::MAX_PLAYERS as usize],
+    };
+    let players = Tournament::MAX_PLAYERS as usize;
+    let tournament = Tournament {
+        organizer: Pubkey::new_unique(),
+        config: config.clone(),
+        settings: settings.clone(),
+        template: Pubkey::new_unique(),
+        state: TournamentState::InProgress,
+        round: u8::MAX,
+        players: vec![
+            TournamentPlayer {
+                pubkey: Pubkey::new_unique(),
+                points: u16::MAX,
+                eliminated_in_round: Some(u8::MAX),
+                last_place: u8::MAX,
+            };
+            players
+        ],
+        // Tables of two, the smallest a round seats
+        tables: vec![
+            TournamentTable {
+                game: Pubkey::new_unique(),
+                seats: vec![u8::MAX; settings.table_size as usize],
+                recorded: true,
+            };
+            players / 2
+        ],
+        pot: u64::MAX,
+        protocol_fee_bps: u16::MAX,
+        fee_collected: true,
+        claimed: u64::MAX,
+        last_activity_at: i64::MAX,
+    };
+
+    assert!(tournament.try_to_vec().unwrap().len() <= Tournament::space(&config, &settings));
+}
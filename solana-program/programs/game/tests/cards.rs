@@ -0,0 +1,66 @@
+use ::risk_game::*;
+
+fn set(types: [CardType; 3]) -> Vec<RiskCard> {
+    types
+        .into_iter()
+        .map(|card_type| RiskCard {
+            card_type,
+            territory_id: None,
+        })
+        .collect()
+}
+
+#[test]
+fn progressive_bonus_climbs_by_five_after_the_sixth_set() {
+    let cards = set([CardType::Infantry, CardType::Infantry, CardType::Infantry]);
+    let bonuses: Vec<u32> = (0..9)
+        .map(|n| card_set_bonus(&CardBonusSchedule::Progressive, n, &cards))
+        .collect();
+
+    assert_eq!(bonuses, vec![4, 6, 8, 10, 12, 15, 20, 25, 30]);
+    assert_eq!(
+        card_set_bonus(&CardBonusSchedule::Progressive, u8::MAX, &cards),
+        1265
+    );
+}
+
+#[test]
+fn fixed_value_bonus_depends_on_the_cards_not_the_count() {
+    let bonus = |types| card_set_bonus(&CardBonusSchedule::FixedValue, 7, &set(types));
+
+    assert_eq!(
+        bonus([CardType::Infantry, CardType::Infantry, CardType::Infantry]),
+        4
+    );
+    assert_eq!(
+        bonus([CardType::Cavalry, CardType::Cavalry, CardType::Cavalry]),
+        6
+    );
+    assert_eq!(
+        bonus([
+            CardType::Artillery,
+            CardType::Artillery,
+            CardType::Artillery
+        ]),
+        8
+    );
+    assert_eq!(
+        bonus([CardType::Infantry, CardType::Cavalry, CardType::Artillery]),
+        10
+    );
+    assert_eq!(
+        bonus([CardType::Infantry, CardType::Infantry, CardType::Wild]),
+        10
+    );
+}
+
+#[test]
+fn custom_bonus_repeats_its_last_entry() {
+    let schedule = CardBonusSchedule::Custom(vec![5, 7, 9]);
+    let cards = set([CardType::Cavalry, CardType::Cavalry, CardType::Cavalry]);
+    let bonuses: Vec<u32> = (0..5)
+        .map(|n| card_set_bonus(&schedule, n, &cards))
+        .collect();
+
+    assert_eq!(bonuses, vec![5, 7, 9, 9, 9]);
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use ::risk_game::*;
+
+#[test]
+fn turns_age_from_when_they_started() {
+    let mut game = common::game(GameState::InProgress, &common::players(2));
+    game.turn_started_at = 1_000;
+
+    assert_eq!(
+        game.idle_for(0, 1_000 + Game::ABANDONED_AFTER),
+        Game::ABANDONED_AFTER
+    );
+    assert!(game.idle_for(0, 999 + Game::ABANDONED_AFTER) < Game::ABANDONED_AFTER);
+}
+
+#[test]
+fn lifting_a_pause_restarts_the_turn_clock() {
+    let mut game = common::game(GameState::InProgress, &common::players(2));
+    game.turn_started_at = 1_000;
+    // Paused for longer than it takes to abandon a game, then lifted
+    let resumed_at = 1_000 + 2 * Game::ABANDONED_AFTER;
+
+    assert_eq!(game.idle_for(resumed_at, resumed_at + 60), 60);
+    assert_eq!(
+        game.idle_for(resumed_at, resumed_at + Game::ABANDONED_AFTER),
+        Game::ABANDONED_AFTER
+    );
+}
+
+#[test]
+fn earlier_pauses_do_not_shorten_later_turns() {
+    let mut game = common::game(GameState::InProgress, &common::players(2));
+    game.turn_started_at = 5_000;
+
+    assert_eq!(game.idle_for(1_000, 5_600), 600);
+}
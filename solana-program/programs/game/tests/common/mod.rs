@@ -0,0 +1,95 @@
+// Fixtures shared by the integration tests; each test crate uses a different subset
+#![allow(dead_code)]
+
+use ::risk_game::*;
+use anchor_lang::prelude::*;
+
+// Standard rules, no stakes
+pub fn config() -> GameConfig {
+    GameConfig {
+        card_bonus_schedule: CardBonusSchedule::Progressive,
+        rules: RuleSet::default(),
+        victory_mode: VictoryMode::WorldDomination,
+        max_turns: None,
+        entry_fee: None,
+        stake_mint: None,
+        prize_split: PrizeSplit::WinnerTakesAll,
+        mission_dealer: None,
+    }
+}
+
+// On turn 1 with the first seat to move, as `init_game` leaves it
+pub fn game(state: GameState, players: &[PlayerState]) -> Game {
+    Game {
+        creator: players
+            .first()
+            .map_or_else(Pubkey::new_unique, |p| p.pubkey),
+        current_player: players.first().map_or_else(Pubkey::default, |p| p.pubkey),
+        turn: 1,
+        state,
+        current_phase: TurnPhase::Reinforcement,
+        cards_sets_turned_in: 0,
+        last_attack_dice: None,
+        pending_reinforcements: None,
+        board: Pubkey::new_unique(),
+        player_account: Pubkey::new_unique(),
+        config: config(),
+        winner: None,
+        ranking: Vec::new(),
+        winning_team: None,
+        fortifications_this_turn: 0,
+        turn_started_at: 0,
+        stats_recorded: false,
+        lobby: false,
+        dealt: false,
+    }
+}
+
+// One seat per color, in color order
+pub fn players(count: usize) -> Vec<PlayerState> {
+    (0..count)
+        .map(|seat| PlayerState::new(Pubkey::new_unique(), seat as u8, None))
+        .collect()
+}
+
+// `territory_count` unowned territories in a ring, each next to the one before and after,
+// grouped into continents of `continent_size` worth 2 armies each
+pub fn board(territory_count: u8, continent_size: u8) -> Board {
+    let territories: Vec<Territory> = (0..territory_count)
+        .map(|id| {
+            let mut adjacent_territories = vec![
+                (id + 1) % territory_count,
+                (id + territory_count - 1) % territory_count,
+            ];
+            adjacent_territories.dedup();
+            Territory {
+                id,
+                continent_id: id / continent_size,
+                owner: None,
+                troops: 1,
+                adjacent_territories,
+            }
+        })
+        .collect();
+    let continents: Vec<Continent> = (0..territory_count.div_ceil(continent_size))
+        .map(|id| Continent {
+            id,
+            territories: (id * continent_size..territory_count.min((id + 1) * continent_size))
+                .collect(),
+            bonus_armies: 2,
+        })
+        .collect();
+
+    let mut board: Board = bytemuck::Zeroable::zeroed();
+    board.append_map(&territories, &continents).unwrap();
+    board
+}
+
+// Hands `territories` to `owner` (neutral if `None`) with `troops` on each
+pub fn hold(board: &mut Board, territories: &[u8], owner: Option<Pubkey>, troops: u16) {
+    for &id in territories {
+        let territory = board.territory_mut(id).unwrap();
+        territory.set_owner(owner);
+        territory.troops = troops;
+    }
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use ::risk_game::*;
+
+fn setup() -> (Game, Board, Vec<PlayerState>) {
+    let players = common::players(3);
+    let game = common::game(GameState::InProgress, &players);
+    (game, common::board(9, 3), players)
+}
+
+#[test]
+fn deal_hands_every_territory_to_a_seat() {
+    let (mut game, mut board, players) = setup();
+    // Whatever the template held is dealt over
+    common::hold(&mut board, &[0, 1, 2], Some(players[0].pubkey), 9);
+
+    deal(&mut game, &mut board, &players).unwrap();
+
+    assert!(game.dealt);
+    assert!(board.territories().iter().all(|t| {
+        t.troops == u16::from(game.config.rules.initial_troops)
+            && players.iter().any(|p| t.owner() == Some(p.pubkey))
+    }));
+}
+
+#[test]
+fn board_is_dealt_only_once() {
+    let (mut game, mut board, players) = setup();
+    deal(&mut game, &mut board, &players).unwrap();
+    let first_deal: Vec<_> = board.territories().iter().map(|t| t.owner()).collect();
+
+    assert_eq!(
+        deal(&mut game, &mut board, &players).unwrap_err(),
+        ErrorCode::AlreadyDealt.into()
+    );
+    let owners: Vec<_> = board.territories().iter().map(|t| t.owner()).collect();
+    assert_eq!(owners, first_deal);
+}
+
+#[test]
+fn no_deal_once_play_has_begun() {
+    let (mut game, mut board, players) = setup();
+    game.pending_reinforcements = Some(3);
+    assert!(deal(&mut game, &mut board, &players).is_err());
+
+    let (mut game, mut board, players) = setup();
+    game.turn = 2;
+    assert!(deal(&mut game, &mut board, &players).is_err());
+    assert!(!game.dealt);
+}
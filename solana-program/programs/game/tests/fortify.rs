@@ -0,0 +1,83 @@
+mod common;
+
+use ::risk_game::*;
+
+fn fortify(from_territory: u8, to_territory: u8, troops: u16) -> FortifyMove {
+    FortifyMove {
+        from_territory,
+        to_territory,
+        troops,
+    }
+}
+
+// A ring of six held by the first of two players, who is in their fortification phase
+fn setup() -> (Vec<PlayerState>, Game, Board) {
+    let players = common::players(2);
+    let mut game = common::game(GameState::InProgress, &players);
+    game.current_phase = TurnPhase::Fortification;
+    let mut board = common::board(6, 3);
+    common::hold(&mut board, &[0, 1, 2, 3, 4, 5], Some(players[0].pubkey), 5);
+    (players, game, board)
+}
+
+#[test]
+fn fortifications_stop_at_the_turn_limit_until_the_next_turn() {
+    let (players, mut game, mut board) = setup();
+    let player = players[0].pubkey;
+    game.config.rules.max_fortifications = Some(2);
+
+    apply_fortify_move(&mut game, &mut board, &player, &[player], &fortify(0, 1, 1)).unwrap();
+    apply_fortify_move(&mut game, &mut board, &player, &[player], &fortify(1, 2, 1)).unwrap();
+    assert_eq!(
+        apply_fortify_move(&mut game, &mut board, &player, &[player], &fortify(2, 3, 1))
+            .unwrap_err(),
+        ErrorCode::FortificationLimitReached.into()
+    );
+
+    next_turn(&mut game, &players, &board, 0);
+    assert_eq!(game.fortifications_this_turn, 0);
+}
+
+#[test]
+fn no_limit_allows_any_number_of_fortifications() {
+    let (players, mut game, mut board) = setup();
+    let player = players[0].pubkey;
+    game.config.rules.max_fortifications = None;
+
+    for _ in 0..300 {
+        apply_fortify_move(&mut game, &mut board, &player, &[player], &fortify(0, 1, 1)).unwrap();
+        apply_fortify_move(&mut game, &mut board, &player, &[player], &fortify(1, 0, 1)).unwrap();
+    }
+    // The counter saturates rather than wrapping
+    assert_eq!(game.fortifications_this_turn, u8::MAX);
+}
+
+#[test]
+fn adjacent_only_refuses_moves_along_a_path() {
+    let (players, mut game, mut board) = setup();
+    let player = players[0].pubkey;
+    game.config.rules.max_fortifications = None;
+
+    apply_fortify_move(&mut game, &mut board, &player, &[player], &fortify(0, 3, 1)).unwrap();
+
+    game.config.rules.adjacent_fortify_only = true;
+    assert_eq!(
+        apply_fortify_move(&mut game, &mut board, &player, &[player], &fortify(0, 3, 1))
+            .unwrap_err(),
+        ErrorCode::TerritoriesNotAdjacent.into()
+    );
+    apply_fortify_move(&mut game, &mut board, &player, &[player], &fortify(0, 5, 1)).unwrap();
+}
+
+#[test]
+fn fortifying_leaves_a_troop_behind() {
+    let (players, mut game, mut board) = setup();
+    let player = players[0].pubkey;
+
+    assert_eq!(
+        apply_fortify_move(&mut game, &mut board, &player, &[player], &fortify(0, 1, 5))
+            .unwrap_err(),
+        ErrorCode::InsufficientTroops.into()
+    );
+    assert_eq!(game.fortifications_this_turn, 0);
+}
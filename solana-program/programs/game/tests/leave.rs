@@ -0,0 +1,54 @@
+mod common;
+
+use ::risk_game::*;
+use anchor_lang::prelude::*;
+
+fn vault(pot: u64, paid_out: u64) -> Vault {
+    Vault {
+        game: Pubkey::new_unique(),
+        pot,
+        paid_out,
+        protocol_fee_bps: 0,
+        fee_collected: false,
+        claimed_seats: 0,
+        bump: 0,
+    }
+}
+
+#[test]
+fn leaving_refunds_the_entry_fee_from_the_pot() {
+    let mut vault = vault(300, 0);
+
+    assert_eq!(vault.take_refund(100).unwrap(), 100);
+    assert_eq!(vault.pot, 200);
+}
+
+#[test]
+fn refund_never_exceeds_what_is_left_unpaid() {
+    let mut vault = vault(300, 250);
+
+    assert_eq!(vault.take_refund(100).unwrap(), 50);
+    assert_eq!(vault.pot, 250);
+    assert_eq!(vault.take_refund(100).unwrap(), 0);
+}
+
+#[test]
+fn refund_rejects_a_vault_paid_out_past_its_pot() {
+    let mut vault = vault(100, 150);
+
+    assert!(vault.take_refund(100).is_err());
+    assert_eq!(vault.pot, 100);
+}
+
+#[test]
+fn slot_rent_covers_exactly_one_seat() {
+    let rent = Rent::default();
+    let rules = common::config().rules;
+
+    for seat in 1..6 {
+        let grown = rent.minimum_balance(8 + PlayerAccount::space(seat + 1, &rules));
+        let shrunk = rent.minimum_balance(8 + PlayerAccount::space(seat, &rules));
+        assert_eq!(player_slot_rent(&rent, seat, &rules), grown - shrunk);
+        assert!(player_slot_rent(&rent, seat, &rules) > 0);
+    }
+}
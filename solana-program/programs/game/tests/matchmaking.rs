@@ -0,0 +1,57 @@
+use ::risk_game::*;
+use anchor_lang::prelude::*;
+
+fn entry(rating: i64, rating_band: u32, enqueued_at: i64) -> QueueEntry {
+    QueueEntry {
+        player: Pubkey::new_unique(),
+        rating: rating * RATING_SCALE,
+        rating_band,
+        enqueued_at,
+    }
+}
+
+#[test]
+fn longest_waiting_players_are_matched_first() {
+    let entries = vec![
+        entry(1500, 100, 30),
+        entry(1500, 100, 10),
+        entry(1520, 100, 20),
+        entry(1480, 100, 40),
+    ];
+
+    assert_eq!(pick_match(&entries, 3), Some(vec![1, 2, 0]));
+}
+
+#[test]
+fn players_outside_everyone_elses_band_are_passed_over() {
+    let entries = vec![
+        // Nobody sits within 50 of the first entry
+        entry(1800, 50, 0),
+        entry(1500, 400, 1),
+        entry(1650, 100, 2),
+        entry(1550, 400, 3),
+        entry(1520, 400, 4),
+    ];
+
+    // The third entry fits the second but not the fourth, which is checked against both
+    assert_eq!(pick_match(&entries, 3), Some(vec![1, 3, 4]));
+    assert_eq!(pick_match(&entries, 4), None);
+}
+
+#[test]
+fn seating_depends_only_on_who_was_matched() {
+    let entries = vec![
+        entry(1500, 100, 0),
+        entry(1500, 100, 1),
+        entry(1500, 100, 2),
+        entry(1500, 100, 3),
+    ];
+    let seats = seat_order(&entries);
+
+    let mut players: Vec<Pubkey> = entries.iter().map(|e| e.player).collect();
+    let mut seated = seats.clone();
+    players.sort();
+    seated.sort();
+    assert_eq!(seated, players);
+    assert_eq!(seat_order(&entries), seats);
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use ::risk_game::*;
+
+// Twelve territories over three continents of four
+fn board() -> Board {
+    common::board(12, 4)
+}
+
+#[test]
+fn missions_asking_for_nothing_are_refused() {
+    let board = board();
+    for mission in [
+        Mission::ConquerContinents(vec![]),
+        Mission::ConquerContinents(vec![0, 7]),
+        Mission::HoldTerritories {
+            count: 0,
+            min_troops: 0,
+        },
+        Mission::EliminateColor {
+            color_id: RuleSet::MAX_PLAYERS,
+            fallback_territories: 12,
+        },
+    ] {
+        assert_eq!(
+            mission.validate(&board, 3).unwrap_err(),
+            ::risk_game::ErrorCode::InvalidMission.into()
+        );
+    }
+}
+
+#[test]
+fn holding_missions_must_beat_an_even_share_of_the_map() {
+    let board = board();
+    let hold = |count| Mission::HoldTerritories {
+        count,
+        min_troops: 2,
+    };
+
+    // Three players are dealt four territories each
+    assert!(hold(4).validate(&board, 3).is_err());
+    assert!(hold(5).validate(&board, 3).is_ok());
+    assert!(Mission::EliminateColor {
+        color_id: 1,
+        fallback_territories: 4,
+    }
+    .validate(&board, 3)
+    .is_err());
+    assert!(Mission::ConquerContinents(vec![0, 2])
+        .validate(&board, 3)
+        .is_ok());
+}
+
+#[test]
+fn continent_missions_name_distinct_continents_on_the_map() {
+    let board = board();
+    let conquer = |ids: &[u8]| Mission::ConquerContinents(ids.to_vec()).validate(&board, 3);
+
+    assert!(conquer(&[]).is_err());
+    assert!(conquer(&[3]).is_err());
+    assert!(conquer(&[1, 1]).is_err());
+    assert!(conquer(&[2, 0, 2]).is_err());
+    assert!(conquer(&[1]).is_ok());
+    assert!(conquer(&[2, 0, 1]).is_ok());
+}
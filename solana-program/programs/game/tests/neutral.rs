@@ -0,0 +1,73 @@
+mod common;
+
+use ::risk_game::*;
+use anchor_lang::prelude::*;
+
+// Fixed keys, so the deal is the same on every run
+fn players(count: u8) -> Vec<PlayerState> {
+    (0..count)
+        .map(|seat| PlayerState::new(Pubkey::new_from_array([seat + 1; 32]), seat, None))
+        .collect()
+}
+
+fn neutral_territories(board: &Board) -> usize {
+    board
+        .territories()
+        .iter()
+        .filter(|t| t.owner().is_none())
+        .count()
+}
+
+#[test]
+fn two_player_games_deal_a_defended_share_to_the_neutral_army() {
+    let players = players(2);
+    let rules = RuleSet::default();
+    let mut board = common::board(42, 6);
+    distribute_territories(&mut board, &players, &rules);
+
+    // Roughly a third each, and neutral territories hold troops to defend with
+    let neutral = neutral_territories(&board);
+    assert!((7..=21).contains(&neutral), "{neutral} neutral territories");
+    assert!(board
+        .territories()
+        .iter()
+        .all(|t| t.troops == u16::from(rules.initial_troops)));
+    for player in &players {
+        assert!(board
+            .territories()
+            .iter()
+            .any(|t| t.owner() == Some(player.pubkey)));
+    }
+}
+
+#[test]
+fn no_neutral_share_with_more_players_or_the_rule_off() {
+    let mut board = common::board(42, 6);
+    distribute_territories(&mut board, &players(3), &RuleSet::default());
+    assert_eq!(neutral_territories(&board), 0);
+
+    let rules = RuleSet {
+        neutral_army: false,
+        ..RuleSet::default()
+    };
+    distribute_territories(&mut board, &players(2), &rules);
+    assert_eq!(neutral_territories(&board), 0);
+}
+
+#[test]
+fn neutral_territories_need_not_be_conquered_to_win() {
+    let players = players(2);
+    let mut board = common::board(4, 2);
+    common::hold(&mut board, &[0, 1], Some(players[0].pubkey), 3);
+    common::hold(&mut board, &[2], Some(players[1].pubkey), 1);
+    common::hold(&mut board, &[3], None, 5);
+    let mut game = common::game(GameState::InProgress, &players);
+
+    check_victory_condition(&mut game, &board, &players);
+    assert!(game.state == GameState::InProgress);
+
+    common::hold(&mut board, &[2], Some(players[0].pubkey), 1);
+    check_victory_condition(&mut game, &board, &players);
+    assert!(game.state == GameState::Completed);
+    assert_eq!(game.winner, Some(players[0].pubkey));
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use ::risk_game::*;
+
+fn game(state: GameState) -> Game {
+    let mut game = common::game(state, &[]);
+    game.turn = 12;
+    game.config.entry_fee = Some(100);
+    game
+}
+
+fn players(eliminated: &[bool]) -> Vec<PlayerState> {
+    let mut players = common::players(eliminated.len());
+    for (player, &eliminated) in players.iter_mut().zip(eliminated) {
+        player.eliminated = eliminated;
+    }
+    players
+}
+
+#[test]
+fn abandoned_games_pay_only_the_seats_still_in_play() {
+    let board: Board = bytemuck::Zeroable::zeroed();
+    let game = game(GameState::Abandoned);
+    assert!(game.is_finished());
+
+    let players = players(&[false, true, false, true]);
+    assert_eq!(
+        prize_shares(&game, &players, &board, 401),
+        vec![(0, 201), (2, 200)]
+    );
+}
+
+#[test]
+fn abandoned_games_with_nobody_left_refund_every_seat() {
+    let board: Board = bytemuck::Zeroable::zeroed();
+    let players = players(&[true, true, true]);
+    assert_eq!(
+        prize_shares(&game(GameState::Abandoned), &players, &board, 300),
+        vec![(0, 100), (1, 100), (2, 100)]
+    );
+}
+
+#[test]
+fn games_still_in_play_cannot_be_settled() {
+    assert!(!game(GameState::Setup).is_finished());
+    assert!(!game(GameState::InProgress).is_finished());
+}
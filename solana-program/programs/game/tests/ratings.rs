@@ -0,0 +1,133 @@
+mod common;
+
+use ::risk_game::*;
+use anchor_lang::prelude::Pubkey;
+
+fn profile() -> PlayerProfile {
+    PlayerProfile {
+        player: Pubkey::new_unique(),
+        games_played: 0,
+        wins: 0,
+        eliminations: 0,
+        territories_conquered: 0,
+        dice_rolled: 0,
+        games_per_color: [0; 6],
+        favorite_color: None,
+        ratings: [PoolRating::default(); RATING_POOLS],
+        bump: 0,
+    }
+}
+
+#[test]
+fn equal_players_move_by_half_k() {
+    let changes = elo_changes(&[INITIAL_RATING, INITIAL_RATING], &[0, 1]);
+
+    assert_eq!(changes, vec![16 * RATING_SCALE, -16 * RATING_SCALE]);
+}
+
+#[test]
+fn upset_moves_more_than_expected_result() {
+    let strong = INITIAL_RATING + 400 * RATING_SCALE;
+    let expected = elo_changes(&[strong, INITIAL_RATING], &[0, 1]);
+    let upset = elo_changes(&[strong, INITIAL_RATING], &[1, 0]);
+
+    assert!(expected[0] > 0 && expected[0] < upset[1]);
+    assert!(upset[0] < -expected[0]);
+}
+
+#[test]
+fn multiplayer_changes_are_zero_sum_up_to_rounding() {
+    let ratings = [
+        INITIAL_RATING,
+        INITIAL_RATING + 120 * RATING_SCALE,
+        INITIAL_RATING - 75 * RATING_SCALE,
+        INITIAL_RATING + 310 * RATING_SCALE,
+    ];
+    let changes = elo_changes(&ratings, &[2, 0, 0, 3]);
+
+    // Tied players draw with each other and both beat the rest
+    assert!(changes[1] > 0 && changes[2] > 0);
+    assert!(changes[3] < 0);
+    assert!(changes.iter().sum::<i64>().abs() <= ratings.len() as i64);
+}
+
+#[test]
+fn unrated_pool_starts_at_initial_rating() {
+    let mut rating = PoolRating::default();
+    assert_eq!(rating.current(), INITIAL_RATING);
+
+    rating.apply(-25 * RATING_SCALE);
+    assert_eq!(rating.current(), INITIAL_RATING - 25 * RATING_SCALE);
+    assert_eq!(rating.games, 1);
+}
+
+#[test]
+fn rating_pool_follows_the_ruleset_and_map() {
+    let map = common::board(6, 3).map_hash();
+    let standard = common::config();
+    let pool = standard.rating_pool(&map);
+
+    // Stakes and payouts don't change how the game plays
+    let staked = GameConfig {
+        entry_fee: Some(1_000),
+        prize_split: PrizeSplit::Ranked(vec![70, 30]),
+        ..common::config()
+    };
+    assert_eq!(staked.rating_pool(&map), pool);
+
+    let missions = GameConfig {
+        victory_mode: VictoryMode::SecretMission,
+        ..common::config()
+    };
+    let house_rules = GameConfig {
+        rules: RuleSet {
+            team_play: true,
+            ..RuleSet::default()
+        },
+        ..common::config()
+    };
+    let timed = GameConfig {
+        max_turns: Some(50),
+        ..common::config()
+    };
+    for config in [missions, house_rules, timed] {
+        assert_ne!(config.rating_pool(&map), pool);
+    }
+
+    let other_map = common::board(8, 4).map_hash();
+    assert_ne!(standard.rating_pool(&other_map), pool);
+}
+
+#[test]
+fn profile_keeps_one_rating_per_pool() {
+    let mut profile = profile();
+    let (first, second) = ([1; 32], [2; 32]);
+
+    profile.rate(first, 40 * RATING_SCALE);
+    profile.rate(first, 10 * RATING_SCALE);
+    profile.rate(second, -20 * RATING_SCALE);
+
+    assert_eq!(profile.rating(&first), INITIAL_RATING + 50 * RATING_SCALE);
+    assert_eq!(profile.rating(&second), INITIAL_RATING - 20 * RATING_SCALE);
+    assert_eq!(profile.rating(&[3; 32]), INITIAL_RATING);
+    // The zeroed key of an unused slot is not a pool
+    assert_eq!(profile.rating(&[0; 32]), INITIAL_RATING);
+}
+
+#[test]
+fn new_pool_replaces_the_least_played_once_full() {
+    let mut profile = profile();
+    // Every pool played twice but the fifth, played once
+    for pool in 0..RATING_POOLS as u8 {
+        let games = if pool == 5 { 1 } else { 2 };
+        for _ in 0..games {
+            profile.rate([pool; 32], RATING_SCALE);
+        }
+    }
+
+    profile.rate([99; 32], -RATING_SCALE);
+
+    assert_eq!(profile.rating(&[99; 32]), INITIAL_RATING - RATING_SCALE);
+    assert_eq!(profile.rating(&[5; 32]), INITIAL_RATING);
+    assert_eq!(profile.rating(&[4; 32]), INITIAL_RATING + 2 * RATING_SCALE);
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use ::risk_game::*;
+
+fn place(territory_id: u8, troops: u16) -> TerritoryPlacement {
+    TerritoryPlacement {
+        territory_id,
+        troops,
+    }
+}
+
+fn troops(board: &Board) -> Vec<u16> {
+    board.territories().iter().map(|t| t.troops).collect()
+}
+
+// Territories 0 to 2 belong to the first of two players, 3 to the second
+fn setup() -> (Vec<PlayerState>, Board) {
+    let players = common::players(2);
+    let mut board = common::board(4, 4);
+    common::hold(&mut board, &[0, 1, 2], Some(players[0].pubkey), 1);
+    common::hold(&mut board, &[3], Some(players[1].pubkey), 1);
+    (players, board)
+}
+
+#[test]
+fn reinforcements_can_be_spread_and_repeated() {
+    let (players, mut board) = setup();
+    let placements = vec![place(0, 2), place(2, 1), place(0, 2)];
+
+    place_troops(&mut board, &players[0].pubkey, placements, 5).unwrap();
+    assert_eq!(troops(&board), vec![5, 1, 2, 1]);
+}
+
+#[test]
+fn placements_must_add_up_to_the_reinforcements() {
+    let (players, mut board) = setup();
+    let player = players[0].pubkey;
+
+    for placements in [vec![place(0, 2)], vec![place(0, 2), place(1, 2)], vec![]] {
+        assert_eq!(
+            place_troops(&mut board, &player, placements, 3).unwrap_err(),
+            ErrorCode::InvalidTroopCount.into()
+        );
+    }
+    assert_eq!(troops(&board), vec![1, 1, 1, 1]);
+}
+
+#[test]
+fn one_bad_placement_places_nothing() {
+    let (players, mut board) = setup();
+    let placements = vec![place(0, 1), place(3, 1), place(1, 1)];
+
+    assert_eq!(
+        place_troops(&mut board, &players[0].pubkey, placements, 3).unwrap_err(),
+        ErrorCode::NotTerritoryOwner.into()
+    );
+    assert_eq!(troops(&board), vec![1, 1, 1, 1]);
+}
+
+#[test]
+fn repeated_fortify_moves_count_once() {
+    let fortify = |from_territory, to_territory, troops| FortifyMove {
+        from_territory,
+        to_territory,
+        troops,
+    };
+    let moves =
+        coalesce_fortify_moves(vec![fortify(0, 1, 2), fortify(1, 2, 1), fortify(0, 1, 3)]).unwrap();
+
+    assert!(moves == vec![fortify(0, 1, 5), fortify(1, 2, 1)]);
+    assert!(
+        coalesce_fortify_moves(vec![fortify(0, 1, u16::MAX), fortify(0, 1, 1)])
+            .is_err_and(|err| err == ErrorCode::TroopOverflow.into())
+    );
+}
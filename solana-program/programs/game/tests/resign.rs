@@ -0,0 +1,99 @@
+mod common;
+
+use ::risk_game::*;
+use anchor_lang::prelude::Pubkey;
+
+// Three seats splitting a six-territory board two apiece, the first to move
+fn setup() -> (Game, Board, Vec<PlayerState>) {
+    let players = common::players(3);
+    let game = common::game(GameState::InProgress, &players);
+    let mut board = common::board(6, 3);
+    for (seat, player) in players.iter().enumerate() {
+        let first = 2 * seat as u8;
+        common::hold(&mut board, &[first, first + 1], Some(player.pubkey), 3);
+    }
+    (game, board, players)
+}
+
+fn owners(board: &Board) -> Vec<Option<Pubkey>> {
+    board.territories().iter().map(|t| t.owner()).collect()
+}
+
+#[test]
+fn resigning_without_an_heir_leaves_territories_neutral() {
+    let (mut game, mut board, mut players) = setup();
+    let quitter = players[1].pubkey;
+
+    forfeit(&mut game, &mut board, &mut players, quitter, None, 50).unwrap();
+
+    assert!(players[1].eliminated);
+    assert_eq!(owners(&board)[2..4], [None, None]);
+    assert_eq!(board.territories()[2].troops, 3);
+    // Not their turn, so it stays with the first seat
+    assert_eq!(game.current_player, players[0].pubkey);
+    assert_eq!(game.turn, 1);
+}
+
+#[test]
+fn heir_inherits_territories_and_capitals() {
+    let (mut game, mut board, mut players) = setup();
+    let quitter = players[1].pubkey;
+    let heir = players[2].pubkey;
+    // The quitter sits on their own capital and the first seat's
+    players[1].capital_holder = Some(quitter);
+    players[0].capital_holder = Some(quitter);
+    players[1].cards.push(RiskCard {
+        card_type: CardType::Wild,
+        territory_id: None,
+    });
+
+    forfeit(&mut game, &mut board, &mut players, quitter, Some(heir), 50).unwrap();
+
+    assert_eq!(owners(&board)[2..4], [Some(heir), Some(heir)]);
+    assert_eq!(players[0].capital_holder, Some(heir));
+    assert_eq!(players[1].capital_holder, Some(heir));
+    assert!(players[1].cards.is_empty());
+    assert!(game.state == GameState::InProgress);
+}
+
+#[test]
+fn resigning_on_your_turn_passes_it_on() {
+    let (mut game, mut board, mut players) = setup();
+    let quitter = players[0].pubkey;
+    game.current_phase = TurnPhase::Attack;
+    game.pending_reinforcements = Some(4);
+
+    forfeit(&mut game, &mut board, &mut players, quitter, None, 50).unwrap();
+
+    assert_eq!(game.current_player, players[1].pubkey);
+    assert_eq!(game.turn, 2);
+    assert_eq!(game.turn_started_at, 50);
+    assert_eq!(game.pending_reinforcements, None);
+    assert!(game.current_phase == TurnPhase::Reinforcement);
+}
+
+#[test]
+fn last_opponent_resigning_ends_the_game() {
+    let (mut game, mut board, mut players) = setup();
+    let (first, second, third) = (players[0].pubkey, players[1].pubkey, players[2].pubkey);
+
+    forfeit(&mut game, &mut board, &mut players, second, Some(first), 50).unwrap();
+    assert!(game.state == GameState::InProgress);
+    forfeit(&mut game, &mut board, &mut players, third, Some(first), 60).unwrap();
+
+    assert!(game.state == GameState::Completed);
+    assert_eq!(game.winner, Some(first));
+}
+
+#[test]
+fn resigning_twice_is_refused() {
+    let (mut game, mut board, mut players) = setup();
+    let quitter = players[1].pubkey;
+
+    forfeit(&mut game, &mut board, &mut players, quitter, None, 50).unwrap();
+
+    assert_eq!(
+        forfeit(&mut game, &mut board, &mut players, quitter, None, 60).unwrap_err(),
+        ErrorCode::PlayerEliminated.into()
+    );
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use ::risk_game::*;
+
+fn with_rules(rules: RuleSet) -> GameConfig {
+    GameConfig {
+        rules,
+        ..common::config()
+    }
+}
+
+#[test]
+fn default_rules_are_the_classic_game() {
+    assert!(validate_game_config(&common::config()).is_ok());
+    let rules = RuleSet::default();
+    assert_eq!(
+        (
+            rules.max_attack_dice,
+            rules.max_defense_dice,
+            rules.card_limit
+        ),
+        (3, 2, 5)
+    );
+}
+
+#[test]
+fn house_rules_outside_their_bounds_are_refused() {
+    let classic = RuleSet::default;
+    for rules in [
+        RuleSet {
+            max_attack_dice: 0,
+            ..classic()
+        },
+        RuleSet {
+            max_defense_dice: RuleSet::MAX_DICE + 1,
+            ..classic()
+        },
+        RuleSet {
+            card_limit: 2,
+            ..classic()
+        },
+        RuleSet {
+            min_players: 1,
+            ..classic()
+        },
+        RuleSet {
+            min_players: 4,
+            max_players: 3,
+            ..classic()
+        },
+        RuleSet {
+            max_players: RuleSet::MAX_PLAYERS + 1,
+            ..classic()
+        },
+        RuleSet {
+            max_fortifications: Some(0),
+            ..classic()
+        },
+    ] {
+        assert_eq!(
+            validate_game_config(&with_rules(rules)).unwrap_err(),
+            ErrorCode::InvalidGameConfig.into()
+        );
+    }
+}
+
+#[test]
+fn house_rules_within_their_bounds_are_accepted() {
+    let rules = RuleSet {
+        max_attack_dice: RuleSet::MAX_DICE,
+        max_defense_dice: 1,
+        card_limit: RuleSet::MAX_CARD_LIMIT,
+        min_players: 3,
+        max_players: 3,
+        max_fortifications: None,
+        adjacent_fortify_only: true,
+        ..RuleSet::default()
+    };
+    assert!(validate_game_config(&with_rules(rules)).is_ok());
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use ::risk_game::*;
+
+// Seats 0 and 1 play as team 0 against seat 2
+fn team_players() -> Vec<PlayerState> {
+    let mut players = common::players(3);
+    for (player, team_id) in players.iter_mut().zip([0, 0, 1]) {
+        player.team_id = Some(team_id);
+    }
+    players
+}
+
+fn team_game(players: &[PlayerState]) -> Game {
+    let mut game = common::game(GameState::InProgress, players);
+    game.config.rules.team_play = true;
+    game.config.rules.max_fortifications = None;
+    game.current_phase = TurnPhase::Fortification;
+    game
+}
+
+fn fortify(from_territory: u8, to_territory: u8, troops: u16) -> FortifyMove {
+    FortifyMove {
+        from_territory,
+        to_territory,
+        troops,
+    }
+}
+
+#[test]
+fn allies_are_teammates_only_in_team_games() {
+    let players = team_players();
+    let allies = allies_of(&players, &players[0].pubkey);
+    assert_eq!(allies, vec![players[0].pubkey, players[1].pubkey]);
+
+    let solo = common::players(2);
+    assert_eq!(allies_of(&solo, &solo[0].pubkey), vec![solo[0].pubkey]);
+}
+
+#[test]
+fn troops_move_onto_and_through_teammates_territories() {
+    let players = team_players();
+    let [a, b, c] = [0, 1, 2].map(|i| players[i].pubkey);
+    let allies = allies_of(&players, &a);
+    let mut game = team_game(&players);
+    // A ring of six: 0 is `a`'s, 1 and 2 `b`'s, 3 neutral, 4 and 5 `c`'s
+    let mut board = common::board(6, 6);
+    common::hold(&mut board, &[0], Some(a), 5);
+    common::hold(&mut board, &[1, 2], Some(b), 1);
+    common::hold(&mut board, &[3], None, 1);
+    common::hold(&mut board, &[4, 5], Some(c), 1);
+
+    apply_fortify_move(&mut game, &mut board, &a, &allies, &fortify(0, 2, 3)).unwrap();
+    assert_eq!(board.territory(0).unwrap().troops, 2);
+    assert_eq!(board.territory(2).unwrap().troops, 4);
+
+    // Only the player's own troops can be moved, and never onto an opponent
+    assert_eq!(
+        apply_fortify_move(&mut game, &mut board, &a, &allies, &fortify(2, 1, 1)).unwrap_err(),
+        ErrorCode::NotTerritoryOwner.into()
+    );
+    assert_eq!(
+        apply_fortify_move(&mut game, &mut board, &a, &allies, &fortify(0, 5, 1)).unwrap_err(),
+        ErrorCode::NotTerritoryOwner.into()
+    );
+}
+
+#[test]
+fn opponents_territories_break_a_fortify_path() {
+    let players = team_players();
+    let [a, b, c] = [0, 1, 2].map(|i| players[i].pubkey);
+    let allies = allies_of(&players, &a);
+    let mut game = team_game(&players);
+    // `b` holds 3, which is only reachable from 0 through `c`'s 1 and 2 or 4 and 5
+    let mut board = common::board(6, 6);
+    common::hold(&mut board, &[0], Some(a), 5);
+    common::hold(&mut board, &[1, 2, 4, 5], Some(c), 1);
+    common::hold(&mut board, &[3], Some(b), 1);
+
+    assert_eq!(
+        apply_fortify_move(&mut game, &mut board, &a, &allies, &fortify(0, 3, 1)).unwrap_err(),
+        ErrorCode::TerritoriesNotConnected.into()
+    );
+}
+
+#[test]
+fn a_team_holding_every_territory_wins_together() {
+    let players = team_players();
+    let [a, b, c] = [0, 1, 2].map(|i| players[i].pubkey);
+    let mut game = team_game(&players);
+    let mut board = common::board(4, 2);
+    common::hold(&mut board, &[0, 1], Some(a), 1);
+    common::hold(&mut board, &[2], Some(b), 1);
+    common::hold(&mut board, &[3], Some(c), 1);
+
+    check_victory_condition(&mut game, &board, &players);
+    assert!(game.state == GameState::InProgress);
+
+    common::hold(&mut board, &[3], Some(b), 1);
+    check_victory_condition(&mut game, &board, &players);
+    assert!(game.state == GameState::Completed);
+    assert_eq!(game.winning_team, Some(0));
+    assert_eq!(game.winner, None);
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use ::risk_game::*;
+use anchor_lang::prelude::*;
+
+fn tournament(format: TournamentFormat, players: Vec<TournamentPlayer>) -> Tournament {
+    Tournament {
+        organizer: Pubkey::new_unique(),
+        config: common::config(),
+        settings: TournamentSettings {
+            format,
+            table_size: 4,
+            max_players: 8,
+            entry_fee: None,
+            prize_split: vec![70, 30],
+        },
+        template: Pubkey::new_unique(),
+        state: TournamentState::Completed,
+        round: 2,
+        players,
+        tables: Vec::new(),
+        pot: 0,
+        protocol_fee_bps: 0,
+        fee_collected: false,
+        claimed: 0,
+        last_activity_at: 0,
+    }
+}
+
+fn player(points: u16, eliminated_in_round: Option<u8>, last_place: u8) -> TournamentPlayer {
+    TournamentPlayer {
+        pubkey: Pubkey::new_unique(),
+        points,
+        eliminated_in_round,
+        last_place,
+    }
+}
+
+#[test]
+fn single_elimination_ranks_by_round_knocked_out_then_table_place() {
+    let tournament = tournament(
+        TournamentFormat::SingleElimination,
+        vec![
+            player(0, Some(1), 3),
+            player(0, Some(2), 1),
+            player(0, None, 0),
+            player(0, Some(1), 2),
+        ],
+    );
+
+    assert_eq!(tournament_standings(&tournament), vec![2, 1, 3, 0]);
+}
+
+#[test]
+fn swiss_ranks_by_points_then_seeding() {
+    let tournament = tournament(
+        TournamentFormat::Swiss { rounds: 2 },
+        vec![
+            player(3, None, 0),
+            player(5, None, 0),
+            player(3, None, 0),
+            player(6, None, 0),
+        ],
+    );
+
+    assert_eq!(tournament_standings(&tournament), vec![3, 1, 0, 2]);
+}
+
+#[test]
+fn cancelled_tournament_refunds_entry_fees_without_protocol_fee() {
+    let mut tournament = tournament(
+        TournamentFormat::SingleElimination,
+        vec![player(0, None, 0), player(0, Some(1), 1)],
+    );
+    tournament.settings.entry_fee = Some(1_000);
+    tournament.pot = 2_000;
+    tournament.protocol_fee_bps = 500;
+    assert_eq!(tournament_payouts(&tournament), vec![(0, 1_330), (1, 570)]);
+
+    tournament.state = TournamentState::Cancelled;
+    assert_eq!(tournament.protocol_fee(), 0);
+    assert_eq!(
+        tournament_payouts(&tournament),
+        vec![(0, 1_000), (1, 1_000)]
+    );
+}
+
+#[test]
+fn table_seats_are_shuffled_by_tournament_round_and_table() {
+    let tournament = Pubkey::new_unique();
+    let entrants: Vec<Pubkey> = (0..6).map(|_| Pubkey::new_unique()).collect();
+
+    let seats = table_seat_order(&tournament, 1, 0, &entrants);
+    let mut sorted = seats.clone();
+    sorted.sort();
+    let mut expected = entrants.clone();
+    expected.sort();
+    assert_eq!(sorted, expected);
+    assert_eq!(table_seat_order(&tournament, 1, 0, &entrants), seats);
+
+    // Registration order doesn't decide who moves first
+    let mut reversed = entrants.clone();
+    reversed.reverse();
+    assert_eq!(table_seat_order(&tournament, 1, 0, &reversed)[0], seats[0]);
+    assert!((2..10).any(|round| table_seat_order(&tournament, round, 0, &entrants) != seats));
+}
+
+// Knocks out every seat of every table but the first, as `record_table_result` would
+fn play_round(tournament: &mut Tournament) {
+    let round = tournament.round;
+    for table in &mut tournament.tables {
+        for (place, &seat) in table.seats.iter().enumerate() {
+            let player = &mut tournament.players[seat as usize];
+            player.last_place = place as u8;
+            if place != 0 {
+                player.eliminated_in_round = Some(round);
+            }
+        }
+        table.recorded = true;
+    }
+}
+
+#[test]
+fn knockout_survivors_below_the_table_minimum_play_a_final() {
+    let mut tournament = tournament(
+        TournamentFormat::SingleElimination,
+        (0..7).map(|_| player(0, None, 0)).collect(),
+    );
+    tournament.config.rules.min_players = 3;
+    tournament.state = TournamentState::Registration;
+    tournament.round = 0;
+
+    advance_round(&mut tournament, 10).unwrap();
+    let sizes: Vec<usize> = tournament.tables.iter().map(|t| t.seats.len()).collect();
+    assert_eq!(sizes, vec![4, 3]);
+    play_round(&mut tournament);
+
+    // Two table winners can't fill a three-seat table, but still meet
+    advance_round(&mut tournament, 20).unwrap();
+    assert!(tournament.state == TournamentState::InProgress);
+    assert_eq!(tournament.tables.len(), 1);
+    assert_eq!(tournament.tables[0].seats, vec![0, 4]);
+    tournament.tables[0].seats.reverse();
+    play_round(&mut tournament);
+
+    advance_round(&mut tournament, 30).unwrap();
+    assert!(tournament.state == TournamentState::Completed);
+    assert_eq!(tournament.round, 2);
+    let undefeated: Vec<usize> = (0..7)
+        .filter(|&i| tournament.players[i].eliminated_in_round.is_none())
+        .collect();
+    assert_eq!(undefeated, vec![4]);
+    assert_eq!(tournament_standings(&tournament)[..2], [4, 0]);
+}
+
+#[test]
+fn knockout_needs_full_first_round_tables() {
+    let mut tournament = tournament(
+        TournamentFormat::SingleElimination,
+        (0..5).map(|_| player(0, None, 0)).collect(),
+    );
+    tournament.config.rules.min_players = 3;
+    tournament.state = TournamentState::Registration;
+    tournament.round = 0;
+
+    assert_eq!(
+        advance_round(&mut tournament, 10).unwrap_err(),
+        ::risk_game::ErrorCode::NotEnoughPlayers.into()
+    );
+}
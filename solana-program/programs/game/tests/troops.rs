@@ -0,0 +1,72 @@
+mod common;
+
+use ::risk_game::*;
+
+#[test]
+fn territories_hold_more_than_a_byte_of_troops_up_to_u16() {
+    let players = common::players(2);
+    let player = players[0].pubkey;
+    let mut board = common::board(2, 2);
+    common::hold(&mut board, &[0, 1], Some(player), 300);
+    let placement = |troops| {
+        vec![TerritoryPlacement {
+            territory_id: 0,
+            troops,
+        }]
+    };
+
+    place_troops(&mut board, &player, placement(1_000), 1_000).unwrap();
+    assert_eq!(board.territory(0).unwrap().troops, 1_300);
+
+    assert_eq!(
+        place_troops(&mut board, &player, placement(u16::MAX), u16::MAX).unwrap_err(),
+        ErrorCode::TroopOverflow.into()
+    );
+    assert_eq!(board.territory(0).unwrap().troops, 1_300);
+}
+
+#[test]
+fn fortifying_past_u16_is_refused() {
+    let players = common::players(2);
+    let player = players[0].pubkey;
+    let mut game = common::game(GameState::InProgress, &players);
+    game.current_phase = TurnPhase::Fortification;
+    let mut board = common::board(2, 2);
+    common::hold(&mut board, &[0], Some(player), 10);
+    common::hold(&mut board, &[1], Some(player), u16::MAX - 5);
+    let fortify = |troops| FortifyMove {
+        from_territory: 0,
+        to_territory: 1,
+        troops,
+    };
+
+    assert_eq!(
+        apply_fortify_move(&mut game, &mut board, &player, &[player], &fortify(6)).unwrap_err(),
+        ErrorCode::TroopOverflow.into()
+    );
+    apply_fortify_move(&mut game, &mut board, &player, &[player], &fortify(5)).unwrap();
+    assert_eq!(board.territory(1).unwrap().troops, u16::MAX);
+}
+
+#[test]
+fn turns_count_past_u16_and_rankings_total_troops_past_it() {
+    let players = common::players(2);
+    let mut board = common::board(4, 4);
+    common::hold(&mut board, &[0, 1], Some(players[0].pubkey), u16::MAX - 1);
+    common::hold(&mut board, &[2, 3], Some(players[1].pubkey), u16::MAX);
+    let mut game = common::game(GameState::InProgress, &players);
+    game.turn = u32::from(u16::MAX);
+
+    next_turn(&mut game, &players, &board, 0);
+    assert_eq!(game.turn, u32::from(u16::MAX) + 1);
+
+    game.config.max_turns = Some(u32::MAX - 1);
+    game.turn = u32::MAX - 1;
+    next_turn(&mut game, &players, &board, 0);
+    assert_eq!(game.turn, u32::MAX);
+    assert_eq!(game.ranking, vec![players[1].pubkey, players[0].pubkey]);
+
+    // The counter saturates rather than wrapping back to turn 0
+    next_turn(&mut game, &players, &board, 0);
+    assert_eq!(game.turn, u32::MAX);
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use ::risk_game::*;
+
+#[test]
+fn turns_pass_to_the_next_seat_still_in_play() {
+    let mut players = common::players(3);
+    players[1].eliminated = true;
+    let board = common::board(6, 3);
+    let mut game = common::game(GameState::InProgress, &players);
+    game.fortifications_this_turn = 1;
+
+    next_turn(&mut game, &players, &board, 100);
+    assert_eq!(game.current_player, players[2].pubkey);
+    assert_eq!((game.turn, game.fortifications_this_turn), (2, 0));
+    assert_eq!(game.turn_started_at, 100);
+
+    next_turn(&mut game, &players, &board, 200);
+    assert_eq!(game.current_player, players[0].pubkey);
+    assert!(game.state == GameState::InProgress);
+}
+
+#[test]
+fn passing_the_turn_limit_ranks_by_territories_then_troops_then_bonuses() {
+    let players = common::players(4);
+    let [a, b, c, d] = [0, 1, 2, 3].map(|i| players[i].pubkey);
+    let mut board = common::board(13, 3);
+    // `b` holds the most territories despite `c`'s larger army. `a` and `d` tie on both,
+    // but `d` holds a whole continent.
+    common::hold(&mut board, &[4, 6, 7, 12], Some(b), 2);
+    common::hold(&mut board, &[0, 1, 3], Some(c), 3);
+    common::hold(&mut board, &[2, 5, 8], Some(a), 1);
+    common::hold(&mut board, &[9, 10, 11], Some(d), 1);
+    let mut game = common::game(GameState::InProgress, &players);
+    game.config.max_turns = Some(20);
+
+    // The last allowed turn is still played
+    game.turn = 19;
+    next_turn(&mut game, &players, &board, 0);
+    assert!(game.state == GameState::InProgress);
+
+    next_turn(&mut game, &players, &board, 0);
+    assert!(game.state == GameState::Completed);
+    assert_eq!(game.ranking, vec![b, c, d, a]);
+    assert_eq!(game.winner, Some(b));
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use ::risk_game::*;
+
+// One seat per entry, each holding the capital it picked
+fn players_with_capitals(capitals: &[Option<u8>]) -> Vec<PlayerState> {
+    let mut players = common::players(capitals.len());
+    for (player, &capital) in players.iter_mut().zip(capitals) {
+        player.capital = capital;
+        player.capital_holder = capital.map(|_| player.pubkey);
+    }
+    players
+}
+
+#[test]
+fn holding_every_capital_wins() {
+    let mut players = players_with_capitals(&[Some(0), Some(5), Some(9)]);
+    let attacker = players[0].pubkey;
+    assert_eq!(capital_winner(&players), None);
+
+    assert_eq!(
+        capture_capital(&mut players, 5, Some(attacker)),
+        Some(players[1].pubkey)
+    );
+    assert_eq!(capital_winner(&players), None);
+
+    capture_capital(&mut players, 9, Some(attacker));
+    assert_eq!(players[2].capital_holder, Some(attacker));
+    assert_eq!(capital_winner(&players), Some(attacker));
+}
+
+#[test]
+fn capitals_must_all_be_chosen_and_held() {
+    let mut players = players_with_capitals(&[Some(0), None]);
+    let attacker = players[0].pubkey;
+    assert_eq!(capital_winner(&players), None);
+
+    // Territories without a capital change nothing
+    assert_eq!(capture_capital(&mut players, 3, Some(attacker)), None);
+
+    // A capital lost to the neutral army is held by nobody
+    players[1] = players_with_capitals(&[Some(4)]).remove(0);
+    capture_capital(&mut players, 4, Some(attacker));
+    capture_capital(&mut players, 0, None);
+    assert_eq!(capital_winner(&players), None);
+}
+
+#[test]
+fn conquering_the_last_territory_eliminates_its_owner() {
+    let mut players = common::players(2);
+    let (attacker, defender) = (players[0].pubkey, players[1].pubkey);
+    let mut board = common::board(2, 2);
+    common::hold(&mut board, &[0, 1], Some(defender), 1);
+    board.territory_mut(0).unwrap().set_owner(Some(attacker));
+
+    // Still holding a territory
+    assert!(!knock_out_if_wiped_out(&board, &mut players, &defender));
+    assert!(!players[1].eliminated);
+    assert_eq!(last_player_standing(&players), None);
+
+    board.territory_mut(1).unwrap().set_owner(Some(attacker));
+    assert!(knock_out_if_wiped_out(&board, &mut players, &defender));
+    assert!(players[1].eliminated);
+    assert_eq!(last_player_standing(&players), Some(attacker));
+
+    // Only counted once
+    assert!(!knock_out_if_wiped_out(&board, &mut players, &defender));
+}
+
+#[test]
+fn resigned_seats_do_not_stand_in_the_way_of_victory() {
+    let mut players = common::players(3);
+    players[0].eliminated = true;
+    assert_eq!(last_player_standing(&players), None);
+
+    players[2].eliminated = true;
+    assert_eq!(last_player_standing(&players), Some(players[1].pubkey));
+
+    players[1].eliminated = true;
+    assert_eq!(last_player_standing(&players), None);
+}
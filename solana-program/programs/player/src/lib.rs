@@ -1,4 +1,6 @@
 use anchor_lang::prelude::*;
+// risk_game's config and game layouts, mirrored once in the territory program
+use territory::risk_game::{self, is_config, is_game_finished, is_paused, CONFIG_SEED};
 
 declare_id!("Crw8PgBMPQ8xHmLnT7oMdp4ePpAcHHPyZeJXZQmAU6Lf");
 
@@ -74,10 +76,25 @@ pub mod player {
             Err(PlayerError::PlayerNotFound.into())
         }
     }
+
+    // Returns the rent to the authority once the game is over
+    pub fn close(_ctx: Context<ClosePlayerState>) -> Result<()> {
+        Ok(())
+    }
 }
 
 #[derive(Accounts)]
 pub struct Initialize<'info> {
+    /// CHECK: risk_game's config PDA, only its discriminator and `paused` byte are read
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+        seeds::program = risk_game::ID,
+        owner = risk_game::ID,
+        constraint = is_config(&protocol_config) @ PlayerError::InvalidConfig,
+        constraint = !is_paused(&protocol_config) @ PlayerError::ProgramPaused
+    )]
+    pub protocol_config: UncheckedAccount<'info>,
     #[account(init, payer = authority, space = 8 + PlayerState::SPACE)]
     pub player_state: Account<'info, PlayerState>,
     #[account(mut)]
@@ -87,11 +104,50 @@ pub struct Initialize<'info> {
 
 #[derive(Accounts)]
 pub struct UpdatePlayers<'info> {
+    /// CHECK: risk_game's config PDA, only its discriminator and `paused` byte are read
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+        seeds::program = risk_game::ID,
+        owner = risk_game::ID,
+        constraint = is_config(&protocol_config) @ PlayerError::InvalidConfig,
+        constraint = !is_paused(&protocol_config) @ PlayerError::ProgramPaused
+    )]
+    pub protocol_config: UncheckedAccount<'info>,
     #[account(mut)]
     pub player_state: Account<'info, PlayerState>,
     pub authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct ClosePlayerState<'info> {
+    /// CHECK: risk_game's config PDA, only its discriminator and `paused` byte are read
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+        seeds::program = risk_game::ID,
+        owner = risk_game::ID,
+        constraint = is_config(&protocol_config) @ PlayerError::InvalidConfig,
+        constraint = !is_paused(&protocol_config) @ PlayerError::ProgramPaused
+    )]
+    pub protocol_config: UncheckedAccount<'info>,
+    /// CHECK: the risk_game game this state belongs to, only its discriminator and `state` byte are read
+    #[account(
+        owner = risk_game::ID,
+        constraint = is_game_finished(&game) @ PlayerError::GameNotFinished
+    )]
+    pub game: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = authority,
+        constraint = player_state.authority == authority.key() @ PlayerError::InvalidAuthority,
+        constraint = player_state.game == game.key() @ PlayerError::GameMismatch
+    )]
+    pub player_state: Account<'info, PlayerState>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
 #[account]
 pub struct PlayerState {
     pub players: Vec<Player>,
@@ -133,6 +189,14 @@ pub enum PlayerError {
     InvalidAuthority,
     #[msg("Player not found")]
     PlayerNotFound,
+    #[msg("Program is paused")]
+    ProgramPaused,
+    #[msg("Game is not finished")]
+    GameNotFinished,
+    #[msg("State belongs to another game")]
+    GameMismatch,
+    #[msg("Protocol config account is invalid")]
+    InvalidConfig,
 }
 
 // Rename CPI module
@@ -143,6 +207,7 @@ pub mod cpi_interface {
 
     pub fn add_player<'info>(
         program: AccountInfo<'info>,
+        protocol_config: AccountInfo<'info>,
         player_state: AccountInfo<'info>,
         authority: AccountInfo<'info>,
         new_player: Player,
@@ -150,18 +215,24 @@ pub mod cpi_interface {
         let ix = anchor_lang::solana_program::instruction::Instruction {
             program_id: program.key(),
             accounts: vec![
+                AccountMeta::new_readonly(protocol_config.key(), false),
                 AccountMeta::new(player_state.key(), false),
                 AccountMeta::new_readonly(authority.key(), true),
             ],
             data: anchor_lang::InstructionData::data(&AddPlayerArgs { new_player }),
         };
 
-        anchor_lang::solana_program::program::invoke_signed(&ix, &[player_state, authority], &[])
-            .map_err(Into::into)
+        anchor_lang::solana_program::program::invoke_signed(
+            &ix,
+            &[protocol_config, player_state, authority],
+            &[],
+        )
+        .map_err(Into::into)
     }
 
     pub fn update_player_cards<'info>(
         program: AccountInfo<'info>,
+        protocol_config: AccountInfo<'info>,
         player_state: AccountInfo<'info>,
         authority: AccountInfo<'info>,
         player_pubkey: Pubkey,
@@ -170,6 +241,7 @@ pub mod cpi_interface {
         let ix = anchor_lang::solana_program::instruction::Instruction {
             program_id: program.key(),
             accounts: vec![
+                AccountMeta::new_readonly(protocol_config.key(), false),
                 AccountMeta::new(player_state.key(), false),
                 AccountMeta::new_readonly(authority.key(), true),
             ],
@@ -179,12 +251,17 @@ pub mod cpi_interface {
             }),
         };
 
-        anchor_lang::solana_program::program::invoke_signed(&ix, &[player_state, authority], &[])
-            .map_err(Into::into)
+        anchor_lang::solana_program::program::invoke_signed(
+            &ix,
+            &[protocol_config, player_state, authority],
+            &[],
+        )
+        .map_err(Into::into)
     }
 
     pub fn set_conquered_territory<'info>(
         program: AccountInfo<'info>,
+        protocol_config: AccountInfo<'info>,
         player_state: AccountInfo<'info>,
         authority: AccountInfo<'info>,
         player_pubkey: Pubkey,
@@ -193,6 +270,7 @@ pub mod cpi_interface {
         let ix = anchor_lang::solana_program::instruction::Instruction {
             program_id: program.key(),
             accounts: vec![
+                AccountMeta::new_readonly(protocol_config.key(), false),
                 AccountMeta::new(player_state.key(), false),
                 AccountMeta::new_readonly(authority.key(), true),
             ],
@@ -202,8 +280,12 @@ pub mod cpi_interface {
             }),
         };
 
-        anchor_lang::solana_program::program::invoke_signed(&ix, &[player_state, authority], &[])
-            .map_err(Into::into)
+        anchor_lang::solana_program::program::invoke_signed(
+            &ix,
+            &[protocol_config, player_state, authority],
+            &[],
+        )
+        .map_err(Into::into)
     }
 
     #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -2,6 +2,53 @@ use anchor_lang::prelude::*;
 
 declare_id!("6YK2ZBHN2tCqbhYFPat3iLqYqSySSiVkMRjSfPUHS9sm");
 
+// The global config lives in risk_game; its `paused` flag halts this program and the
+// player program too. risk_game depends on both, so the layout they read is mirrored
+// here, once, and checked against the real accounts in risk_game's account_space tests.
+pub mod risk_game {
+    use super::*;
+
+    declare_id!("2c6oia9bbPKJk1tV3PvYUULuiktyGF7hTpAZsndAo3ij");
+
+    pub const CONFIG_SEED: &[u8] = b"config";
+    // Anchor discriminator of risk_game's `Config` account
+    pub const CONFIG_DISCRIMINATOR: [u8; 8] = [155, 12, 170, 224, 30, 250, 204, 130];
+    // Offset of `Config::paused`, its last field: discriminator, admin, treasury,
+    // protocol_fee_bps, bump, resumed_at
+    pub const CONFIG_PAUSED_OFFSET: usize = 8 + 32 + 32 + 2 + 1 + 8;
+
+    // Guards the `paused` read against an account laid out differently
+    pub fn is_config(config: &AccountInfo) -> bool {
+        config.try_borrow_data().is_ok_and(|data| {
+            data.starts_with(&CONFIG_DISCRIMINATOR) && data.len() == CONFIG_PAUSED_OFFSET + 1
+        })
+    }
+
+    pub fn is_paused(config: &AccountInfo) -> bool {
+        config
+            .try_borrow_data()
+            .map_or(true, |data| data.get(CONFIG_PAUSED_OFFSET) != Some(&0))
+    }
+
+    // Anchor discriminator of risk_game's `Game` account
+    pub const GAME_DISCRIMINATOR: [u8; 8] = [27, 90, 166, 125, 74, 100, 121, 18];
+    // Offset of `Game::state`: discriminator, creator, current_player, turn
+    pub const GAME_STATE_OFFSET: usize = 8 + 32 + 32 + 4;
+    // `GameState::Completed`, `GameState::Aborted` and `GameState::Abandoned`
+    pub const GAME_FINISHED_STATES: &[u8] = &[2, 3, 4];
+
+    pub fn is_game_finished(game: &AccountInfo) -> bool {
+        game.try_borrow_data().is_ok_and(|data| {
+            data.starts_with(&GAME_DISCRIMINATOR)
+                && data
+                    .get(GAME_STATE_OFFSET)
+                    .is_some_and(|state| GAME_FINISHED_STATES.contains(state))
+        })
+    }
+}
+
+use risk_game::{is_config, is_game_finished, is_paused, CONFIG_SEED};
+
 #[program]
 pub mod territory {
     use super::*;
@@ -24,7 +71,7 @@ pub mod territory {
         ctx: Context<UpdateTerritory>,
         territory_id: u8,
         owner: Option<Pubkey>,
-        troops: u8,
+        troops: u16,
     ) -> Result<()> {
         let territory_state = &mut ctx.accounts.territory_state;
         require!(
@@ -72,11 +119,31 @@ pub mod territory {
 
         Ok(false)
     }
+
+    // Closes the map state and refunds its rent once the game is over
+    pub fn close(_ctx: Context<CloseTerritoryState>) -> Result<()> {
+        Ok(())
+    }
 }
 
 #[derive(Accounts)]
+#[instruction(territories: Vec<Territory>, continents: Vec<Continent>)]
 pub struct Initialize<'info> {
-    #[account(init, payer = authority, space = 8 + TerritoryState::SPACE)]
+    /// CHECK: risk_game's config PDA, only its discriminator and `paused` byte are read
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+        seeds::program = risk_game::ID,
+        owner = risk_game::ID,
+        constraint = is_config(&protocol_config) @ TerritoryError::InvalidConfig,
+        constraint = !is_paused(&protocol_config) @ TerritoryError::ProgramPaused
+    )]
+    pub protocol_config: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + TerritoryState::space(&territories, &continents)
+    )]
     pub territory_state: Account<'info, TerritoryState>,
     #[account(mut)]
     pub authority: Signer<'info>,
@@ -85,6 +152,16 @@ pub struct Initialize<'info> {
 
 #[derive(Accounts)]
 pub struct UpdateTerritory<'info> {
+    /// CHECK: risk_game's config PDA, only its discriminator and `paused` byte are read
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+        seeds::program = risk_game::ID,
+        owner = risk_game::ID,
+        constraint = is_config(&protocol_config) @ TerritoryError::InvalidConfig,
+        constraint = !is_paused(&protocol_config) @ TerritoryError::ProgramPaused
+    )]
+    pub protocol_config: UncheckedAccount<'info>,
     #[account(mut)]
     pub territory_state: Account<'info, TerritoryState>,
     pub authority: Signer<'info>,
@@ -100,6 +177,35 @@ pub struct CheckConnection<'info> {
     pub territory_state: Account<'info, TerritoryState>,
 }
 
+#[derive(Accounts)]
+pub struct CloseTerritoryState<'info> {
+    /// CHECK: risk_game's config PDA, only its discriminator and `paused` byte are read
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+        seeds::program = risk_game::ID,
+        owner = risk_game::ID,
+        constraint = is_config(&protocol_config) @ TerritoryError::InvalidConfig,
+        constraint = !is_paused(&protocol_config) @ TerritoryError::ProgramPaused
+    )]
+    pub protocol_config: UncheckedAccount<'info>,
+    /// CHECK: the risk_game game this state belongs to, only its discriminator and `state` byte are read
+    #[account(
+        owner = risk_game::ID,
+        constraint = is_game_finished(&game) @ TerritoryError::GameNotFinished
+    )]
+    pub game: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = authority,
+        constraint = territory_state.authority == authority.key() @ TerritoryError::InvalidAuthority,
+        constraint = territory_state.game == game.key() @ TerritoryError::GameMismatch
+    )]
+    pub territory_state: Account<'info, TerritoryState>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
 #[account]
 pub struct TerritoryState {
     pub territories: Vec<Territory>,
@@ -113,7 +219,7 @@ pub struct Territory {
     pub id: u8,
     pub continent_id: u8,
     pub owner: Option<Pubkey>,
-    pub troops: u8,
+    pub troops: u16,
     pub adjacent_territories: Vec<u8>,
 }
 
@@ -125,16 +231,44 @@ pub struct Continent {
 }
 
 impl TerritoryState {
-    pub const SPACE: usize = 4 + (50 * 42) + // territories vec (42 territories, ~50 bytes each)
-        4 + (20 * 6) + // continents vec (6 continents, ~20 bytes each)
+    pub fn space(territories: &[Territory], continents: &[Continent]) -> usize {
+        4 + territories.iter().map(Territory::space).sum::<usize>() + // territories vec
+        4 + continents.iter().map(Continent::space).sum::<usize>() + // continents vec
         32 + // game pubkey
-        32; // authority pubkey
+        32 // authority pubkey
+    }
+}
+
+impl Territory {
+    pub fn space(&self) -> usize {
+        1 + // id
+        1 + // continent_id
+        1 + 32 + // owner Option
+        2 + // troops
+        4 + self.adjacent_territories.len() // adjacent_territories vec
+    }
+}
+
+impl Continent {
+    pub fn space(&self) -> usize {
+        1 + // id
+        4 + self.territories.len() + // territories vec
+        1 // bonus_armies
+    }
 }
 
 #[error_code]
 pub enum TerritoryError {
     #[msg("Invalid authority")]
     InvalidAuthority,
+    #[msg("Program is paused")]
+    ProgramPaused,
+    #[msg("Game is not finished")]
+    GameNotFinished,
+    #[msg("State belongs to another game")]
+    GameMismatch,
+    #[msg("Protocol config account is invalid")]
+    InvalidConfig,
 }
 
 // CPI Interface
@@ -145,15 +279,17 @@ pub mod cpi_interface {
 
     pub fn update_territory<'info>(
         program: AccountInfo<'info>,
+        protocol_config: AccountInfo<'info>,
         territory_state: AccountInfo<'info>,
         authority: AccountInfo<'info>,
         territory_id: u8,
         owner: Option<Pubkey>,
-        troops: u8,
+        troops: u16,
     ) -> Result<()> {
         let ix = anchor_lang::solana_program::instruction::Instruction {
             program_id: program.key(),
             accounts: vec![
+                AccountMeta::new_readonly(protocol_config.key(), false),
                 AccountMeta::new(territory_state.key(), false),
                 AccountMeta::new_readonly(authority.key(), true),
             ],
@@ -164,8 +300,12 @@ pub mod cpi_interface {
             }),
         };
 
-        anchor_lang::solana_program::program::invoke_signed(&ix, &[territory_state, authority], &[])
-            .map_err(Into::into)
+        anchor_lang::solana_program::program::invoke_signed(
+            &ix,
+            &[protocol_config, territory_state, authority],
+            &[],
+        )
+        .map_err(Into::into)
     }
 
     pub fn are_territories_connected<'info>(
@@ -194,7 +334,7 @@ pub mod cpi_interface {
     pub struct UpdateTerritoryArgs {
         pub territory_id: u8,
         pub owner: Option<Pubkey>,
-        pub troops: u8,
+        pub troops: u16,
     }
 
     impl anchor_lang::InstructionData for UpdateTerritoryArgs {}
@@ -0,0 +1,187 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program, BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { expect } from "chai";
+import { RiskGame } from "../target/types/risk_game";
+
+// Must match `Board::SPACE` plus the 8 byte discriminator
+const BOARD_ACCOUNT_SIZE = 8 + 13926;
+const ENTRY_FEE = 10_000_000;
+const BPF_LOADER_UPGRADEABLE_ID = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+
+describe("matchmaking", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.RiskGame as Program<RiskGame>;
+  const cranker = (provider.wallet as anchor.Wallet).payer;
+  const players = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+
+  const pda = (...seeds: Buffer[]) =>
+    PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+  const protocolConfig = pda(Buffer.from("config"));
+  const profileOf = (wallet: PublicKey) => pda(Buffer.from("profile"), wallet.toBuffer());
+
+  const config = {
+    cardBonusSchedule: { progressive: {} },
+    rules: {
+      minReinforcements: 3,
+      initialTroops: 3,
+      maxAttackDice: 3,
+      maxDefenseDice: 2,
+      cardLimit: 5,
+      minPlayers: 2,
+      maxPlayers: 6,
+      neutralArmy: true,
+      teamPlay: false,
+      teamAttacks: false,
+      maxFortifications: 1,
+      adjacentFortifyOnly: false,
+    },
+    victoryMode: { worldDomination: {} },
+    maxTurns: null,
+    entryFee: new BN(ENTRY_FEE),
+    stakeMint: null,
+    prizeSplit: { winnerTakesAll: {} },
+    missionDealer: null,
+  };
+
+  const territories = [
+    { id: 0, continentId: 0, owner: null, troops: 0, adjacentTerritories: Buffer.from([1]) },
+    { id: 1, continentId: 0, owner: null, troops: 0, adjacentTerritories: Buffer.from([0]) },
+  ];
+  const continents = [{ id: 0, territories: Buffer.from([0, 1]), bonusArmies: 2 }];
+
+  const queue = Keypair.generate();
+  const template = Keypair.generate();
+
+  const createBoard = async (board: Keypair) =>
+    SystemProgram.createAccount({
+      fromPubkey: cranker.publicKey,
+      newAccountPubkey: board.publicKey,
+      space: BOARD_ACCOUNT_SIZE,
+      lamports: await provider.connection.getMinimumBalanceForRentExemption(BOARD_ACCOUNT_SIZE),
+      programId: program.programId,
+    });
+
+  const enqueue = (player: Keypair, ratingBand = 200) =>
+    program.methods
+      .enqueue(ratingBand)
+      .accounts({
+        protocolConfig,
+        queue: queue.publicKey,
+        playerProfile: profileOf(player.publicKey),
+        player: player.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([player])
+      .rpc();
+
+  before(async () => {
+    for (const player of players) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(player.publicKey, 1_000_000_000)
+      );
+    }
+
+    // The config is global, so reuse it if another suite already created it
+    if (!(await provider.connection.getAccountInfo(protocolConfig))) {
+      const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+      );
+      await program.methods
+        .initializeConfig(cranker.publicKey, 0)
+        .accounts({
+          config: protocolConfig,
+          program: program.programId,
+          programData,
+          admin: cranker.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    await program.methods
+      .createQueue(config, 2, territories, continents)
+      .accounts({
+        protocolConfig,
+        queue: queue.publicKey,
+        template: template.publicKey,
+        creator: cranker.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions([await createBoard(template)])
+      .signers([queue, template])
+      .rpc();
+  });
+
+  it("seats two queued players in a started game with their stakes", async () => {
+    await enqueue(players[0]);
+    await enqueue(players[1]);
+
+    const game = Keypair.generate();
+    const board = Keypair.generate();
+    const playerAccount = Keypair.generate();
+    const vault = pda(Buffer.from("vault"), game.publicKey.toBuffer());
+    const listing = pda(Buffer.from("listing"), game.publicKey.toBuffer());
+
+    await program.methods
+      .matchPlayers()
+      .accounts({
+        protocolConfig,
+        queue: queue.publicKey,
+        template: template.publicKey,
+        game: game.publicKey,
+        board: board.publicKey,
+        playerAccount: playerAccount.publicKey,
+        vault,
+        listing,
+        cranker: cranker.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions([await createBoard(board)])
+      .signers([game, board, playerAccount])
+      .rpc();
+
+    // Seating is shuffled from the match, and the first seat moves first
+    const seated = (await program.account.playerAccount.fetch(playerAccount.publicKey)).players.map(
+      (p) => p.pubkey.toBase58()
+    );
+    expect(seated).to.have.members([players[0].publicKey.toBase58(), players[1].publicKey.toBase58()]);
+    const started = await program.account.game.fetch(game.publicKey);
+    expect(started.state).to.deep.equal({ inProgress: {} });
+    expect(started.currentPlayer.toBase58()).to.equal(seated[0]);
+    expect((await program.account.vault.fetch(vault)).pot.toNumber()).to.equal(2 * ENTRY_FEE);
+    expect((await program.account.matchQueue.fetch(queue.publicKey)).entries).to.be.empty;
+  });
+
+  it("refunds a player who leaves the queue", async () => {
+    const player = players[2];
+    await enqueue(player);
+    const before = await provider.connection.getBalance(player.publicKey);
+
+    await program.methods
+      .leaveQueue()
+      .accounts({ protocolConfig, queue: queue.publicKey, player: player.publicKey })
+      .signers([player])
+      .rpc();
+
+    expect(await provider.connection.getBalance(player.publicKey)).to.equal(before + ENTRY_FEE);
+  });
+
+  it("refuses to expire an entry before its timeout", async () => {
+    const player = players[2];
+    await enqueue(player);
+
+    try {
+      await program.methods
+        .expireQueueEntry()
+        .accounts({ protocolConfig, queue: queue.publicKey, player: player.publicKey })
+        .rpc();
+      expect.fail("entry should not expire yet");
+    } catch (err) {
+      expect((err as anchor.AnchorError).error.errorCode.code).to.equal("QueueEntryNotExpired");
+    }
+  });
+});
@@ -0,0 +1,368 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program, BN } from "@coral-xyz/anchor";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  createMint,
+  createAccount,
+  mintTo,
+  getAccount,
+  getAssociatedTokenAddressSync,
+} from "@solana/spl-token";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { expect } from "chai";
+import { RiskGame } from "../target/types/risk_game";
+
+// Must match `Board::SPACE` plus the 8 byte discriminator
+const BOARD_ACCOUNT_SIZE = 8 + 13926;
+const ENTRY_FEE = 1_000;
+const PROTOCOL_FEE_BPS = 500;
+const BPF_LOADER_UPGRADEABLE_ID = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+
+describe("token stakes", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.RiskGame as Program<RiskGame>;
+  const creator = (provider.wallet as anchor.Wallet).payer;
+  const challenger = Keypair.generate();
+  const treasury = Keypair.generate();
+  const [protocolConfig] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+
+  let mint: PublicKey;
+  let creatorTokens: PublicKey;
+  let challengerTokens: PublicKey;
+  let treasuryTokens: PublicKey;
+
+  const profileOf = (wallet: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("profile"), wallet.toBuffer()],
+      program.programId
+    )[0];
+
+  const config = (stakeMint: PublicKey, minPlayers: number) => ({
+    cardBonusSchedule: { progressive: {} },
+    rules: {
+      minReinforcements: 3,
+      initialTroops: 3,
+      maxAttackDice: 3,
+      maxDefenseDice: 2,
+      cardLimit: 5,
+      minPlayers,
+      maxPlayers: 6,
+      neutralArmy: true,
+      teamPlay: false,
+      teamAttacks: false,
+      maxFortifications: 1,
+      adjacentFortifyOnly: false,
+    },
+    victoryMode: { worldDomination: {} },
+    maxTurns: null,
+    entryFee: new BN(ENTRY_FEE),
+    stakeMint,
+    prizeSplit: { winnerTakesAll: {} },
+    missionDealer: null,
+  });
+
+  const territories = [
+    { id: 0, continentId: 0, owner: null, troops: 0, adjacentTerritories: Buffer.from([1]) },
+    { id: 1, continentId: 0, owner: null, troops: 0, adjacentTerritories: Buffer.from([0]) },
+  ];
+  const continents = [{ id: 0, territories: Buffer.from([0, 1]), bonusArmies: 2 }];
+
+  const listingOf = (game: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("listing"), game.toBuffer()],
+      program.programId
+    )[0];
+
+  const balance = async (tokenAccount: PublicKey) =>
+    Number((await getAccount(provider.connection, tokenAccount)).amount);
+
+  // Creates a game staked in `mint` and returns its accounts
+  const createGame = async (minPlayers = 2) => {
+    const game = Keypair.generate();
+    const board = Keypair.generate();
+    const playerAccount = Keypair.generate();
+    const [vault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), game.publicKey.toBuffer()],
+      program.programId
+    );
+    const vaultTokenAccount = getAssociatedTokenAddressSync(mint, vault, true);
+    const listing = listingOf(game.publicKey);
+
+    const createBoard = SystemProgram.createAccount({
+      fromPubkey: creator.publicKey,
+      newAccountPubkey: board.publicKey,
+      space: BOARD_ACCOUNT_SIZE,
+      lamports: await provider.connection.getMinimumBalanceForRentExemption(BOARD_ACCOUNT_SIZE),
+      programId: program.programId,
+    });
+
+    await program.methods
+      .initializeGame("red", territories, continents, config(mint, minPlayers), null)
+      .accounts({
+        protocolConfig,
+        game: game.publicKey,
+        board: board.publicKey,
+        playerAccount: playerAccount.publicKey,
+        creatorProfile: profileOf(creator.publicKey),
+        listing,
+        vault,
+        stakeMint: mint,
+        vaultTokenAccount,
+        creatorTokenAccount: creatorTokens,
+        creator: creator.publicKey,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .preInstructions([createBoard])
+      .signers([game, board, playerAccount])
+      .rpc();
+
+    return { game, board, playerAccount, listing, vault, vaultTokenAccount };
+  };
+
+  const join = (accounts: Awaited<ReturnType<typeof createGame>>) =>
+    program.methods
+      .joinGame("blue", null)
+      .accounts({
+        protocolConfig,
+        game: accounts.game.publicKey,
+        playerAccount: accounts.playerAccount.publicKey,
+        playerProfile: profileOf(challenger.publicKey),
+        listing: accounts.listing,
+        vault: accounts.vault,
+        vaultTokenAccount: accounts.vaultTokenAccount,
+        player: challenger.publicKey,
+        playerTokenAccount: challengerTokens,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([challenger])
+      .rpc();
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(challenger.publicKey, 1_000_000_000)
+    );
+
+    mint = await createMint(provider.connection, creator, creator.publicKey, null, 0);
+    creatorTokens = await createAccount(provider.connection, creator, mint, creator.publicKey);
+    challengerTokens = await createAccount(provider.connection, creator, mint, challenger.publicKey);
+    await mintTo(provider.connection, creator, mint, creatorTokens, creator, 10 * ENTRY_FEE);
+    await mintTo(provider.connection, creator, mint, challengerTokens, creator, 10 * ENTRY_FEE);
+    treasuryTokens = await createAccount(provider.connection, creator, mint, treasury.publicKey);
+
+    // The config is global, so reuse it if another suite already created it
+    if (await provider.connection.getAccountInfo(protocolConfig)) {
+      await program.methods
+        .updateConfig(treasury.publicKey, PROTOCOL_FEE_BPS)
+        .accounts({ config: protocolConfig, admin: creator.publicKey })
+        .rpc();
+    } else {
+      const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+      );
+      await program.methods
+        .initializeConfig(treasury.publicKey, PROTOCOL_FEE_BPS)
+        .accounts({
+          config: protocolConfig,
+          program: program.programId,
+          programData,
+          admin: creator.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+  });
+
+  it("escrows stakes and pays the pot to the winner minus the protocol fee", async () => {
+    const accounts = await createGame();
+    let listing = await program.account.gameListing.fetch(accounts.listing);
+    expect(listing.state).to.deep.equal({ setup: {} });
+    expect(listing.playerCount).to.equal(1);
+
+    await join(accounts);
+    listing = await program.account.gameListing.fetch(accounts.listing);
+    expect(listing.state).to.deep.equal({ inProgress: {} });
+    expect(listing.playerCount).to.equal(2);
+    expect(await balance(accounts.vaultTokenAccount)).to.equal(2 * ENTRY_FEE);
+
+    // The challenger concedes, leaving the creator as the last player standing
+    await program.methods
+      .resign(null)
+      .accounts({
+        protocolConfig,
+        game: accounts.game.publicKey,
+        board: accounts.board.publicKey,
+        playerAccount: accounts.playerAccount.publicKey,
+        listing: accounts.listing,
+        player: challenger.publicKey,
+      })
+      .signers([challenger])
+      .rpc();
+    listing = await program.account.gameListing.fetch(accounts.listing);
+    expect(listing.state).to.deep.equal({ completed: {} });
+
+    const profileBefore = await program.account.playerProfile.fetch(profileOf(creator.publicKey));
+    await program.methods
+      .recordGameStats()
+      .accounts({
+        protocolConfig,
+        game: accounts.game.publicKey,
+        board: accounts.board.publicKey,
+        playerAccount: accounts.playerAccount.publicKey,
+      })
+      .remainingAccounts(
+        [creator.publicKey, challenger.publicKey].map((wallet) => ({
+          pubkey: profileOf(wallet),
+          isWritable: true,
+          isSigner: false,
+        }))
+      )
+      .rpc();
+    const profile = await program.account.playerProfile.fetch(profileOf(creator.publicKey));
+    expect(profile.wins).to.equal(profileBefore.wins + 1);
+    const ratedGames = (p: typeof profile) => p.ratings.reduce((sum, r) => sum + r.games, 0);
+    expect(ratedGames(profile)).to.equal(ratedGames(profileBefore) + 1);
+    expect(profile.favoriteColor).to.equal(0);
+
+    const before = await balance(creatorTokens);
+    await program.methods
+      .claimWinnings()
+      .accounts({
+        protocolConfig,
+        game: accounts.game.publicKey,
+        board: accounts.board.publicKey,
+        playerAccount: accounts.playerAccount.publicKey,
+        vault: accounts.vault,
+        vaultTokenAccount: accounts.vaultTokenAccount,
+        payee: creator.publicKey,
+        payeeTokenAccount: creatorTokens,
+        treasury: treasury.publicKey,
+        treasuryTokenAccount: treasuryTokens,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+    const fee = (2 * ENTRY_FEE * PROTOCOL_FEE_BPS) / 10_000;
+    expect(await balance(treasuryTokens)).to.equal(fee);
+    expect(await balance(creatorTokens)).to.equal(before + 2 * ENTRY_FEE - fee);
+
+    const challengerLamports = await provider.connection.getBalance(challenger.publicKey);
+    await program.methods
+      .closeGame()
+      .accounts({
+        protocolConfig,
+        game: accounts.game.publicKey,
+        board: accounts.board.publicKey,
+        playerAccount: accounts.playerAccount.publicKey,
+        listing: accounts.listing,
+        vault: accounts.vault,
+        vaultTokenAccount: accounts.vaultTokenAccount,
+        gameResult: null,
+        playerState: null,
+        playerProgram: null,
+        territoryState: null,
+        territoryProgram: null,
+        creator: creator.publicKey,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      // The challenger paid for their player slot when they joined
+      .remainingAccounts([{ pubkey: challenger.publicKey, isWritable: true, isSigner: false }])
+      .rpc();
+    expect(await provider.connection.getAccountInfo(accounts.vaultTokenAccount)).to.be.null;
+    expect(await provider.connection.getBalance(challenger.publicKey)).to.be.greaterThan(
+      challengerLamports
+    );
+  });
+
+  it("refunds every stake in full when the lobby is cancelled", async () => {
+    const creatorBefore = await balance(creatorTokens);
+    const challengerBefore = await balance(challengerTokens);
+
+    // A third seat keeps the lobby open after the challenger joins
+    const accounts = await createGame(3);
+    await join(accounts);
+    await program.methods
+      .cancelGame()
+      .accounts({
+        protocolConfig,
+        game: accounts.game.publicKey,
+        board: accounts.board.publicKey,
+        playerAccount: accounts.playerAccount.publicKey,
+        listing: accounts.listing,
+        vault: accounts.vault,
+        vaultTokenAccount: accounts.vaultTokenAccount,
+        creator: creator.publicKey,
+        creatorTokenAccount: creatorTokens,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts([
+        { pubkey: challenger.publicKey, isWritable: true, isSigner: false },
+        { pubkey: challengerTokens, isWritable: true, isSigner: false },
+      ])
+      .rpc();
+
+    expect(await balance(creatorTokens)).to.equal(creatorBefore);
+    expect(await balance(challengerTokens)).to.equal(challengerBefore);
+  });
+
+  it("refunds every stake when the admin force-ends a game", async () => {
+    const creatorBefore = await balance(creatorTokens);
+    const challengerBefore = await balance(challengerTokens);
+
+    const accounts = await createGame();
+    await join(accounts);
+    await program.methods
+      .forceEndGame()
+      .accounts({
+        protocolConfig,
+        game: accounts.game.publicKey,
+        playerAccount: accounts.playerAccount.publicKey,
+        listing: accounts.listing,
+        vault: accounts.vault,
+        vaultTokenAccount: accounts.vaultTokenAccount,
+        admin: creator.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts([
+        { pubkey: creator.publicKey, isWritable: true, isSigner: false },
+        { pubkey: creatorTokens, isWritable: true, isSigner: false },
+        { pubkey: challenger.publicKey, isWritable: true, isSigner: false },
+        { pubkey: challengerTokens, isWritable: true, isSigner: false },
+      ])
+      .rpc();
+
+    const game = await program.account.game.fetch(accounts.game.publicKey);
+    expect(game.state).to.deep.equal({ aborted: {} });
+    expect(await balance(creatorTokens)).to.equal(creatorBefore);
+    expect(await balance(challengerTokens)).to.equal(challengerBefore);
+  });
+
+  it("rejects joins while paused", async () => {
+    const accounts = await createGame();
+    await program.methods
+      .setPaused(true)
+      .accounts({ config: protocolConfig, admin: creator.publicKey })
+      .rpc();
+
+    try {
+      await join(accounts);
+      expect.fail("join should be rejected while paused");
+    } catch (err) {
+      expect((err as anchor.AnchorError).error.errorCode.code).to.equal("ProgramPaused");
+    } finally {
+      await program.methods
+        .setPaused(false)
+        .accounts({ config: protocolConfig, admin: creator.publicKey })
+        .rpc();
+    }
+  });
+});
@@ -0,0 +1,285 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program, BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { expect } from "chai";
+import { RiskGame } from "../target/types/risk_game";
+
+// Must match `Board::SPACE` plus the 8 byte discriminator
+const BOARD_ACCOUNT_SIZE = 8 + 13926;
+const ENTRY_FEE = 10_000_000;
+const BPF_LOADER_UPGRADEABLE_ID = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+
+describe("tournament", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.RiskGame as Program<RiskGame>;
+  const organizer = (provider.wallet as anchor.Wallet).payer;
+  const players = [Keypair.generate(), Keypair.generate()];
+
+  const pda = (...seeds: Buffer[]) =>
+    PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+  const protocolConfig = pda(Buffer.from("config"));
+
+  const config = {
+    cardBonusSchedule: { progressive: {} },
+    rules: {
+      minReinforcements: 3,
+      initialTroops: 3,
+      maxAttackDice: 3,
+      maxDefenseDice: 2,
+      cardLimit: 5,
+      minPlayers: 2,
+      maxPlayers: 6,
+      neutralArmy: true,
+      teamPlay: false,
+      teamAttacks: false,
+      maxFortifications: 1,
+      adjacentFortifyOnly: false,
+    },
+    victoryMode: { worldDomination: {} },
+    maxTurns: null,
+    entryFee: null,
+    stakeMint: null,
+    prizeSplit: { winnerTakesAll: {} },
+    missionDealer: null,
+  };
+
+  const settings = {
+    format: { singleElimination: {} },
+    tableSize: 4,
+    maxPlayers: 8,
+    entryFee: new BN(ENTRY_FEE),
+    prizeSplit: Buffer.from([100]),
+  };
+
+  const territories = [
+    { id: 0, continentId: 0, owner: null, troops: 0, adjacentTerritories: Buffer.from([1]) },
+    { id: 1, continentId: 0, owner: null, troops: 0, adjacentTerritories: Buffer.from([0]) },
+  ];
+  const continents = [{ id: 0, territories: Buffer.from([0, 1]), bonusArmies: 2 }];
+
+  const tournament = Keypair.generate();
+  const template = Keypair.generate();
+
+  const createBoard = async (board: Keypair) =>
+    SystemProgram.createAccount({
+      fromPubkey: organizer.publicKey,
+      newAccountPubkey: board.publicKey,
+      space: BOARD_ACCOUNT_SIZE,
+      lamports: await provider.connection.getMinimumBalanceForRentExemption(BOARD_ACCOUNT_SIZE),
+      programId: program.programId,
+    });
+
+  before(async () => {
+    for (const player of players) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(player.publicKey, 1_000_000_000)
+      );
+    }
+
+    // The config is global, so reuse it if another suite already created it
+    if (!(await provider.connection.getAccountInfo(protocolConfig))) {
+      const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+      );
+      await program.methods
+        .initializeConfig(organizer.publicKey, 0)
+        .accounts({
+          config: protocolConfig,
+          program: program.programId,
+          programData,
+          admin: organizer.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+  });
+
+  it("runs a two player knockout and pays the champion", async () => {
+    await program.methods
+      .createTournament(config, settings, territories, continents)
+      .accounts({
+        protocolConfig,
+        tournament: tournament.publicKey,
+        template: template.publicKey,
+        organizer: organizer.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions([await createBoard(template)])
+      .signers([tournament, template])
+      .rpc();
+
+    for (const player of players) {
+      await program.methods
+        .registerForTournament()
+        .accounts({
+          protocolConfig,
+          tournament: tournament.publicKey,
+          player: player.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([player])
+        .rpc();
+    }
+
+    const startRound = () =>
+      program.methods
+        .startRound()
+        .accounts({
+          protocolConfig,
+          tournament: tournament.publicKey,
+          organizer: organizer.publicKey,
+        })
+        .rpc();
+    await startRound();
+
+    const game = Keypair.generate();
+    const board = Keypair.generate();
+    const playerAccount = Keypair.generate();
+    await program.methods
+      .spawnTable(0)
+      .accounts({
+        protocolConfig,
+        tournament: tournament.publicKey,
+        template: template.publicKey,
+        game: game.publicKey,
+        board: board.publicKey,
+        playerAccount: playerAccount.publicKey,
+        vault: pda(Buffer.from("vault"), game.publicKey.toBuffer()),
+        listing: pda(Buffer.from("listing"), game.publicKey.toBuffer()),
+        organizer: organizer.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions([await createBoard(board)])
+      .signers([game, board, playerAccount])
+      .rpc();
+
+    // The second seed concedes the table
+    await program.methods
+      .resign(null)
+      .accounts({
+        protocolConfig,
+        game: game.publicKey,
+        board: board.publicKey,
+        playerAccount: playerAccount.publicKey,
+        listing: pda(Buffer.from("listing"), game.publicKey.toBuffer()),
+        player: players[1].publicKey,
+      })
+      .signers([players[1]])
+      .rpc();
+
+    await program.methods
+      .recordTableResult(0)
+      .accounts({
+        protocolConfig,
+        tournament: tournament.publicKey,
+        game: game.publicKey,
+        board: board.publicKey,
+        playerAccount: playerAccount.publicKey,
+      })
+      .rpc();
+    await startRound();
+
+    const finished = await program.account.tournament.fetch(tournament.publicKey);
+    expect(finished.state).to.deep.equal({ completed: {} });
+    expect(finished.players[1].eliminatedInRound).to.equal(1);
+
+    const { treasury } = await program.account.config.fetch(protocolConfig);
+    const before = await provider.connection.getBalance(players[0].publicKey);
+    await program.methods
+      .claimTournamentPrize()
+      .accounts({
+        protocolConfig,
+        tournament: tournament.publicKey,
+        payee: players[0].publicKey,
+        treasury,
+      })
+      .rpc();
+
+    const pot = 2 * ENTRY_FEE;
+    const fee = Math.floor((pot * finished.protocolFeeBps) / 10_000);
+    expect(await provider.connection.getBalance(players[0].publicKey)).to.equal(
+      before + pot - fee
+    );
+
+    await program.methods
+      .closeTournament()
+      .accounts({
+        protocolConfig,
+        tournament: tournament.publicKey,
+        template: template.publicKey,
+        organizer: organizer.publicKey,
+      })
+      .rpc();
+    expect(await provider.connection.getAccountInfo(tournament.publicKey)).to.be.null;
+  });
+
+  it("refunds entry fees when the organizer cancels during registration", async () => {
+    const cancelled = Keypair.generate();
+    const cancelledTemplate = Keypair.generate();
+    await program.methods
+      .createTournament(config, settings, territories, continents)
+      .accounts({
+        protocolConfig,
+        tournament: cancelled.publicKey,
+        template: cancelledTemplate.publicKey,
+        organizer: organizer.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions([await createBoard(cancelledTemplate)])
+      .signers([cancelled, cancelledTemplate])
+      .rpc();
+
+    const balances = [];
+    for (const player of players) {
+      balances.push(await provider.connection.getBalance(player.publicKey));
+      await program.methods
+        .registerForTournament()
+        .accounts({
+          protocolConfig,
+          tournament: cancelled.publicKey,
+          player: player.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([player])
+        .rpc();
+    }
+
+    // The second player leaves before the start and is refunded at once
+    await program.methods
+      .leaveTournament()
+      .accounts({ protocolConfig, tournament: cancelled.publicKey, player: players[1].publicKey })
+      .signers([players[1]])
+      .rpc();
+    expect(await provider.connection.getBalance(players[1].publicKey)).to.equal(balances[1]);
+
+    await program.methods
+      .cancelTournament()
+      .accounts({ protocolConfig, tournament: cancelled.publicKey, signer: organizer.publicKey })
+      .rpc();
+
+    const { treasury } = await program.account.config.fetch(protocolConfig);
+    await program.methods
+      .claimTournamentPrize()
+      .accounts({
+        protocolConfig,
+        tournament: cancelled.publicKey,
+        payee: players[0].publicKey,
+        treasury,
+      })
+      .rpc();
+    expect(await provider.connection.getBalance(players[0].publicKey)).to.equal(balances[0]);
+
+    await program.methods
+      .closeTournament()
+      .accounts({
+        protocolConfig,
+        tournament: cancelled.publicKey,
+        template: cancelledTemplate.publicKey,
+        organizer: organizer.publicKey,
+      })
+      .rpc();
+  });
+});